@@ -1,3 +1,8 @@
+pub mod cd;
 pub mod clone;
+pub mod init;
+pub mod list;
 pub mod open;
+pub mod setup;
 pub mod tmux;
+pub mod worktree;
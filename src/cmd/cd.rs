@@ -0,0 +1,121 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo::{Config as MatcherConfig, Matcher};
+
+use crate::Config;
+use crate::frecency::{self, Frecency};
+use crate::repo::discovery::Discovery;
+use crate::repo::index::{Change, Index};
+use crate::ui::select::Select;
+
+/// Prints the path of a repository picked under the root, for the function from `sdev init` to cd
+/// into
+///
+/// The picker starts with the indexed repositories and lists the ones a refresh finds as they're
+/// found. A `query` matching a single repository picks it without asking, once every repository
+/// is known.
+pub fn run(query: &Option<String>, config: &Config) -> anyhow::Result<()> {
+    let index = Index::load(Discovery::from_config(config)?)?;
+
+    // repositories removed since the index was saved are left out before the refresh notices
+    let mut paths: Vec<PathBuf> = index
+        .repos()
+        .filter(|repo| repo.path().is_dir())
+        .map(|repo| repo.relative_path(&config.root).to_owned())
+        .collect();
+    let query = query.as_deref().unwrap_or_default();
+
+    let (found_tx, found_rx) = mpsc::channel();
+    let root = config.root.clone();
+    let refresh = thread::spawn(move || refresh(index, &root, &found_tx));
+
+    if !query.is_empty() {
+        refresh
+            .join()
+            .map_err(|_| anyhow::anyhow!("repo discovery panicked"))??;
+        paths.extend(found_rx.try_iter());
+    }
+
+    let picked = match unique_match(&paths, query) {
+        Some(path) => Some(path),
+        None => {
+            let repos = Frecency::repos()?;
+            let root = config.root.clone();
+            let now = frecency::now();
+
+            Select::new("Repository", paths, |path, _| path.to_string_lossy().into())
+                .with_query(query)
+                .with_bias(move |path| repos.score(&root.join(path).to_string_lossy(), now))
+                .with_more(found_rx)
+                .on_stderr()
+                .run()?
+        }
+    };
+
+    // a refresh still running is dropped with the process rather than delaying the cd, and is
+    // done again next time
+    let Some(path) = picked else {
+        return Ok(());
+    };
+    let path = config.root.join(path);
+
+    let mut repos = Frecency::repos()?;
+    repos.record(&path.to_string_lossy(), frecency::now());
+    repos.save()?;
+
+    println!("{}", path.display());
+
+    Ok(())
+}
+
+/// Sends the repositories a refresh of `index` finds, relative to `root`, then saves it
+fn refresh(mut index: Index, root: &Path, found: &Sender<PathBuf>) -> io::Result<()> {
+    index.refresh(|change| {
+        if let Change::Added(repo) = change {
+            let _ = found.send(repo.relative_path(root).to_owned());
+        }
+    });
+
+    index.save()
+}
+
+fn unique_match(paths: &[PathBuf], query: &str) -> Option<PathBuf> {
+    if query.is_empty() {
+        return None;
+    }
+
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+    let mut matcher = Matcher::new(MatcherConfig::DEFAULT);
+
+    let haystacks = paths.iter().map(|path| path.to_string_lossy());
+
+    match &pattern.match_list(haystacks, &mut matcher)[..] {
+        [(path, _)] => Some(PathBuf::from(path.as_ref())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_query_with_one_match_is_picked() {
+        let paths = vec![
+            PathBuf::from("github.com/rails/rails"),
+            PathBuf::from("github.com/ruby/ruby"),
+        ];
+
+        assert_eq!(
+            Some(PathBuf::from("github.com/ruby/ruby")),
+            unique_match(&paths, "ruby/ruby")
+        );
+        assert_eq!(None, unique_match(&paths, "github"));
+        assert_eq!(None, unique_match(&paths, "python"));
+        assert_eq!(None, unique_match(&paths, ""));
+    }
+}
@@ -1,52 +1,201 @@
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
+use std::iter;
 use std::path::{Path, PathBuf};
+use std::thread;
+
+use anyhow::{anyhow, bail};
 
 use gix::Url;
 use gix::url::Scheme;
 
 use crate::Config;
-use crate::dep::Dep;
+use crate::cmd::setup;
+use crate::dep::executor::Executor;
 use crate::dep::git::Clone;
-use crate::repo::GitRepoSource;
+use crate::dep::plan::Plan;
+use crate::dep::report::Report;
+use crate::repo::discovery::Discovery;
+use crate::repo::index::Index;
+use crate::repo::probe::{self, Probe};
+use crate::repo::rewrite::Rewrites;
+use crate::repo::{GitRepoSource, classify};
+use crate::ui::select::select;
 
-pub fn run(source: &GitRepoSource, config: &Config) -> anyhow::Result<()> {
-    let url = url_for(source, config);
+pub fn run(source: &GitRepoSource, config: &Config, dry_run: bool) -> anyhow::Result<()> {
+    let rewrites = Rewrites::from_git_config(&gix::config::File::from_globals()?)
+        .with_mirrors(&config.mirrors);
+    let source = &resolve(source, config, &rewrites)?;
+    let urls = url_for(source, config, &rewrites)?;
     let path = path_for(source, config);
+    let clone = Clone::new(urls.fetch, path.clone())
+        .with_push_url(urls.push)
+        .with_layout(config.layout)
+        .with_retry(config.fetch_retry);
+
+    if dry_run {
+        print!("{}", Plan::new(&clone));
+        return setup::run_in(&clone.workdir(), dry_run);
+    }
+
+    let report = Report::default();
+    let result = Executor::default().with_reporter(&report).run(&clone);
+
+    print!("{report}");
+
+    result?;
+
+    if let Some(kind) = classify(&path) {
+        let mut index = Index::load(Discovery::from_config(config)?)?;
+        index.insert(path, kind);
+        index.save()?;
+    }
+
+    setup::run_in(&clone.workdir(), dry_run)
+}
+
+/// Qualifies a short name with the owner it exists under, asking when it exists under several
+///
+/// Without any extra `owners` configured, names belong to `user` and nothing is probed.
+fn resolve(
+    source: &GitRepoSource,
+    config: &Config,
+    rewrites: &Rewrites,
+) -> anyhow::Result<GitRepoSource> {
+    let GitRepoSource::Name(name) = source else {
+        return Ok(source.clone());
+    };
+
+    if config.owners.is_empty() {
+        return Ok(source.clone());
+    }
+
+    let candidates = candidates_for(name, config);
+    let probes = thread::scope(|s| {
+        let probes: Vec<_> = candidates
+            .iter()
+            .map(|candidate| {
+                s.spawn(|| {
+                    let source = GitRepoSource::Path(candidate.clone());
+
+                    url_for(&source, config, rewrites)
+                        .map_or(Probe::Missing, |urls| probe::check(&urls.fetch))
+                })
+            })
+            .collect();
+
+        probes
+            .into_iter()
+            .map(|probe| probe.join().unwrap_or(Probe::Missing))
+            .collect::<Vec<_>>()
+    });
+
+    // owners that wouldn't say are only worth trying when no owner has the repository publicly
+    let wanted = if probes.contains(&Probe::Found) {
+        Probe::Found
+    } else {
+        Probe::Unknown
+    };
+
+    let mut hits: Vec<String> = candidates
+        .into_iter()
+        .zip(probes)
+        .filter_map(|(candidate, probe)| (probe == wanted).then_some(candidate))
+        .collect();
+
+    let path = match hits.len() {
+        0 => bail!(
+            "no repository named {name} under {}",
+            owners(config).cloned().collect::<Vec<_>>().join(", ")
+        ),
+        1 => hits.remove(0),
+        _ if !io::stdin().is_terminal() => hits.remove(0),
+        _ => select(
+            &format!("{name} exists under several owners:"),
+            hits,
+            |path, _| path.as_str().into(),
+        )?
+        .ok_or_else(|| anyhow!("no owner selected"))?,
+    };
 
-    Clone::new(url, path).process()?;
+    Ok(GitRepoSource::Path(path))
+}
+
+fn owners(config: &Config) -> impl Iterator<Item = &String> {
+    let mut seen = HashSet::new();
+
+    iter::once(&config.user)
+        .chain(&config.owners)
+        .filter(move |owner| seen.insert(*owner))
+}
+
+/// `owner/name` paths to look for a short name under
+fn candidates_for(name: &str, config: &Config) -> Vec<String> {
+    owners(config)
+        .map(|owner| format!("{owner}/{name}"))
+        .collect()
+}
+
+struct RemoteUrls {
+    fetch: Url,
+    /// Only set when pushes should go somewhere other than `fetch`
+    push: Option<Url>,
+}
 
-    Ok(())
+/// The URLs to clone from and push to, after applying mirrors and git's URL rewrites
+///
+/// Pushes go to the canonical URL, so fetching from a read-only mirror doesn't break `git push`.
+fn url_for(
+    source: &GitRepoSource,
+    config: &Config,
+    rewrites: &Rewrites,
+) -> anyhow::Result<RemoteUrls> {
+    let canonical = canonical_url_for(source, config);
+    let fetch = rewrites.fetch_url(&canonical)?;
+    let push = rewrites.push_url(&canonical)?;
+
+    Ok(RemoteUrls {
+        push: (push != fetch).then_some(push),
+        fetch,
+    })
 }
 
-fn url_for(source: &GitRepoSource, config: &Config) -> Url {
-    match source {
-        GitRepoSource::Name(s) => Url::from_parts(
+fn canonical_url_for(source: &GitRepoSource, config: &Config) -> Url {
+    let (host, path) = match source {
+        GitRepoSource::Name(s) => (&config.host, format!("{}/{s}.git", config.user)),
+        GitRepoSource::Path(s) => (&config.host, format!("{s}.git")),
+        GitRepoSource::HostPath { host, path } => (host, format!("{path}.git")),
+        GitRepoSource::Url { url, .. } => return url.clone(),
+    };
+
+    match config.protocol.scheme() {
+        Scheme::Ssh => Url::from_parts(
             Scheme::Ssh,
             Some("git".to_string()),
             None,
-            Some(config.host.clone()),
+            Some(host.clone()),
             None,
-            format!("{}/{s}.git", config.user).into(),
+            path.into(),
             true,
-        )
-        .expect("error constructing repo URL"),
-        GitRepoSource::Path(s) => Url::from_parts(
-            Scheme::Ssh,
-            Some("git".to_string()),
+        ),
+        scheme => Url::from_parts(
+            scheme,
             None,
-            Some(config.host.clone()),
             None,
-            format!("{s}.git").into(),
-            true,
-        )
-        .expect("error constructing repo URL"),
-        GitRepoSource::Url { url, .. } => url.clone(),
+            Some(host.clone()),
+            None,
+            format!("/{path}").into(),
+            false,
+        ),
     }
+    .expect("error constructing repo URL")
 }
 
 fn path_for(source: &GitRepoSource, config: &Config) -> PathBuf {
     config.root.join(match source {
         GitRepoSource::Name(s) => Path::new(&config.host).join(&config.user).join(s),
         GitRepoSource::Path(s) => Path::new(&config.host).join(s),
+        GitRepoSource::HostPath { host, path } => Path::new(host).join(path),
         GitRepoSource::Url { host, path, .. } => Path::new(host).join(path),
     })
 }
@@ -54,6 +203,8 @@ fn path_for(source: &GitRepoSource, config: &Config) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Protocol;
+    use crate::repo::rewrite::Rule;
 
     #[test]
     fn url_and_path_use_host_and_user_when_given_name() {
@@ -73,6 +224,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn url_and_path_use_given_host_when_given_host_path() {
+        assert_source_to_url_and_path(
+            "gitlab.com/group/sub/repo",
+            "git@gitlab.com:group/sub/repo.git",
+            "/home/skipkayhil/src/gitlab.com/group/sub/repo",
+        );
+    }
+
+    #[test]
+    fn url_and_path_use_forge_host_when_given_shorthand() {
+        assert_source_to_url_and_path(
+            "sr:~sircmpwn/scdoc",
+            "git@git.sr.ht:~sircmpwn/scdoc.git",
+            "/home/skipkayhil/src/git.sr.ht/~sircmpwn/scdoc",
+        );
+    }
+
     #[test]
     fn url_and_path_are_constructed_when_given_url() {
         assert_source_to_url_and_path(
@@ -97,9 +266,81 @@ mod tests {
             host: "github.com".to_string(),
             root: PathBuf::from("/home/skipkayhil/src"),
             user: "skipkayhil".to_string(),
+            protocol: Protocol::Ssh,
+            ..Config::default()
         };
 
-        assert_eq!(expected_url, url_for(&source, &config).to_string());
+        let urls = url_for(&source, &config, &Rewrites::default()).unwrap();
+
+        assert_eq!(expected_url, urls.fetch.to_string());
+        assert_eq!(None, urls.push);
         assert_eq!(PathBuf::from(expected_path), path_for(&source, &config));
     }
+
+    #[test]
+    fn url_uses_https_when_preferred() {
+        let source: GitRepoSource = "ruby/ruby".parse().unwrap();
+        let config = Config {
+            protocol: Protocol::Https,
+            ..Config::default()
+        };
+
+        let urls = url_for(&source, &config, &Rewrites::default()).unwrap();
+
+        assert_eq!("https://github.com/ruby/ruby.git", urls.fetch.to_string());
+    }
+
+    #[test]
+    fn candidates_try_user_first_then_owners_in_order() {
+        let config = Config {
+            user: "skipkayhil".to_string(),
+            owners: vec!["acme".into(), "skipkayhil".into(), "rails".into()],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            vec!["skipkayhil/friday", "acme/friday", "rails/friday"],
+            candidates_for("friday", &config)
+        );
+    }
+
+    #[test]
+    fn names_are_not_probed_without_owners() {
+        let source: GitRepoSource = "friday".parse().unwrap();
+
+        assert!(matches!(
+            resolve(&source, &Config::default(), &Rewrites::default()).unwrap(),
+            GitRepoSource::Name(name) if name == "friday"
+        ));
+    }
+
+    #[test]
+    fn mirrored_url_pushes_to_canonical_url_and_keeps_path() {
+        let source: GitRepoSource = "rails/rails".parse().unwrap();
+        let config = Config {
+            root: PathBuf::from("/home/skipkayhil/src"),
+            protocol: Protocol::Ssh,
+            mirrors: vec![Rule {
+                base: "https://mirror.internal/".into(),
+                instead_of: "git@github.com:".into(),
+            }],
+            ..Config::default()
+        };
+        let rewrites = Rewrites::default().with_mirrors(&config.mirrors);
+
+        let urls = url_for(&source, &config, &rewrites).unwrap();
+
+        assert_eq!(
+            "https://mirror.internal/rails/rails.git",
+            urls.fetch.to_string()
+        );
+        assert_eq!(
+            Some("git@github.com:rails/rails.git".to_string()),
+            urls.push.map(|url| url.to_string())
+        );
+        assert_eq!(
+            PathBuf::from("/home/skipkayhil/src/github.com/rails/rails"),
+            path_for(&source, &config)
+        );
+    }
 }
@@ -0,0 +1,19 @@
+use clap::ValueEnum;
+
+#[derive(Clone, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Prints a `sdev` function for `shell` that changes directory after `sdev cd`, which a subprocess
+/// can't do for its shell
+pub fn run(shell: &Shell) {
+    let script = match shell {
+        Shell::Bash | Shell::Zsh => include_str!("init/sdev.sh"),
+        Shell::Fish => include_str!("init/sdev.fish"),
+    };
+
+    print!("{script}");
+}
@@ -0,0 +1,8 @@
+# sdev init fish | source
+function sdev
+    if test "$argv[1]" = cd
+        set -l dir (command sdev $argv); and test -n "$dir"; and builtin cd -- $dir
+    else
+        command sdev $argv
+    end
+end
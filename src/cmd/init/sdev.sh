@@ -0,0 +1,9 @@
+# eval "$(sdev init bash)" or eval "$(sdev init zsh)"
+sdev() {
+    if [ "$1" = cd ]; then
+        local dir
+        dir="$(command sdev "$@")" && [ -n "$dir" ] && builtin cd -- "$dir"
+    else
+        command sdev "$@"
+    fi
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::Config;
+use crate::repo::GitRepo;
+use crate::repo::discovery::Discovery;
+use crate::repo::index::Index;
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    path: PathBuf,
+    host: Option<String>,
+    owner: Option<String>,
+    remotes: BTreeMap<String, String>,
+    branch: Option<String>,
+}
+
+impl Entry {
+    fn new(repo: &GitRepo, root: &Path) -> Self {
+        let (host, owner) = host_and_owner(repo.relative_path(root));
+        let (remotes, branch) = match gix::open(repo.path()) {
+            Ok(git) => (remotes(&git), branch(&git)),
+            Err(_) => (BTreeMap::new(), None),
+        };
+
+        Self {
+            name: repo.name().into(),
+            path: repo.path().into(),
+            host,
+            owner,
+            remotes,
+            branch,
+        }
+    }
+}
+
+/// Prints every repository under the root, one relative path per line unless `absolute` or `json`
+///
+/// JSON output is one object per line, always with absolute paths.
+pub fn run(absolute: bool, json: bool, config: &Config) -> anyhow::Result<()> {
+    let mut index = Index::load(Discovery::from_config(config)?)?;
+    index.refresh(|_| ());
+    index.save()?;
+
+    let mut stdout = io::stdout().lock();
+
+    for repo in index.repos() {
+        let result = if json {
+            let entry = serde_json::to_string(&Entry::new(&repo, &config.root))?;
+            writeln!(stdout, "{entry}")
+        } else if absolute {
+            writeln!(stdout, "{}", repo.path().display())
+        } else {
+            writeln!(stdout, "{}", repo.relative_path(&config.root).display())
+        };
+
+        // the reader went away, like `sdev list | head`
+        if let Err(e) = result {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// The host and owner of a repository cloned to `<host>/<owner>/<name>`, where the owner can span
+/// several directories like GitLab subgroups
+fn host_and_owner(relative_path: &Path) -> (Option<String>, Option<String>) {
+    let components: Vec<_> = relative_path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+
+    match components.as_slice() {
+        [host, owner @ .., _name] if !owner.is_empty() => {
+            (Some(host.to_string()), Some(owner.join("/")))
+        }
+        _ => (None, None),
+    }
+}
+
+fn remotes(repo: &gix::Repository) -> BTreeMap<String, String> {
+    repo.remote_names()
+        .iter()
+        .filter_map(|name| {
+            let remote = repo.find_remote(name.as_ref()).ok()?;
+            let url = remote.url(gix::remote::Direction::Fetch)?;
+
+            Some((name.to_string(), url.to_bstring().to_string()))
+        })
+        .collect()
+}
+
+fn branch(repo: &gix::Repository) -> Option<String> {
+    let head = repo.head_name().ok()??;
+
+    Some(head.shorten().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::executor::Executor;
+    use crate::dep::git::Remote;
+    use crate::repo::RepoKind;
+
+    #[test]
+    fn host_and_owner_come_from_the_clone_path() {
+        assert_eq!(
+            (Some("github.com".into()), Some("rails".into())),
+            host_and_owner(Path::new("github.com/rails/rails"))
+        );
+        assert_eq!(
+            (Some("gitlab.com".into()), Some("group/subgroup".into())),
+            host_and_owner(Path::new("gitlab.com/group/subgroup/project"))
+        );
+        assert_eq!((None, None), host_and_owner(Path::new("scratch/demo")));
+    }
+
+    #[test]
+    fn entries_include_remotes_and_branch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let path = root.join("github.com/rails/rails");
+        gix::init(&path).unwrap();
+
+        let url = gix::Url::try_from("https://github.com/rails/rails.git").unwrap();
+        Executor::default()
+            .run(&Remote::new(path.clone(), "origin".into(), url))
+            .unwrap();
+
+        let entry = Entry::new(&GitRepo::at(&path, RepoKind::Normal).unwrap(), root);
+
+        assert_eq!("rails", entry.name);
+        assert_eq!(
+            Some(&"https://github.com/rails/rails.git".to_string()),
+            entry.remotes.get("origin")
+        );
+        assert!(entry.branch.is_some());
+    }
+}
@@ -0,0 +1,91 @@
+use std::env;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use crate::dep::executor::Executor;
+use crate::dep::plan::Plan;
+use crate::dep::report::Report;
+use crate::recipe::trust::TrustStore;
+use crate::recipe::{FILE_NAME, Recipe};
+
+pub fn run(path: &Option<PathBuf>, dry_run: bool) -> anyhow::Result<()> {
+    let repo = match path {
+        Some(path) => path.clone(),
+        None => {
+            let repo = gix::discover(env::current_dir()?)?;
+
+            repo.workdir().ok_or(anyhow!("No worktree"))?.to_owned()
+        }
+    };
+
+    if !repo.join(FILE_NAME).exists() {
+        println!("No {FILE_NAME} in {}", repo.display());
+        return Ok(());
+    }
+
+    run_in(&repo, dry_run)
+}
+
+/// Runs the repository's recipe if it has one, asking the user to trust it the first time
+///
+/// A dry run prints the plan of any recipe without asking, since nothing in it runs.
+pub fn run_in(repo: &Path, dry_run: bool) -> anyhow::Result<()> {
+    let Some(recipe) = Recipe::load(repo)? else {
+        return Ok(());
+    };
+
+    let setup = recipe.setup(repo);
+
+    if dry_run {
+        print!("{}", Plan::new(&setup));
+        return Ok(());
+    }
+
+    if !ensure_trusted(repo, &recipe)? {
+        println!("Skipping untrusted {FILE_NAME} in {}", repo.display());
+        return Ok(());
+    }
+
+    let report = Report::default();
+    let result = Executor::default().with_reporter(&report).run(&setup);
+
+    print!("{report}");
+
+    result
+}
+
+/// Whether the user trusts the recipe, asking on a terminal if they haven't before
+pub fn ensure_trusted(repo: &Path, recipe: &Recipe) -> anyhow::Result<bool> {
+    let store = TrustStore::default();
+
+    if store.is_trusted(repo, recipe.contents())? {
+        return Ok(true);
+    }
+
+    let stdin = io::stdin();
+
+    if !stdin.is_terminal() {
+        return Ok(false);
+    }
+
+    println!(
+        "{} contains a recipe sdev hasn't run before:\n",
+        repo.join(FILE_NAME).display()
+    );
+    println!("{}", recipe.contents().trim_end());
+    print!("\nTrust and run it? [y/N] ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        return Ok(false);
+    }
+
+    store.trust(repo, recipe.contents())?;
+
+    Ok(true)
+}
@@ -1,20 +1,116 @@
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Instant;
 
+use anyhow::bail;
 use clap::ValueEnum;
-use ratatui_crossterm::crossterm::event::{self, KeyCode, KeyEventKind};
-use walkdir::WalkDir;
+use nucleo::Utf32String;
+use ratatui_crossterm::crossterm::event::{self, KeyCode, KeyEventKind, KeyModifiers};
 
-use crate::repo::GitRepo;
-use crate::shell::tmux::{Session, list_sessions};
+use crate::Config;
+use crate::cmd::setup::ensure_trusted;
+use crate::cmd::tmux::preview::Previews;
+use crate::frecency::{self, Frecency};
+use crate::recipe::Recipe;
+use crate::repo::discovery::Discovery;
+use crate::repo::index::{Change, Index};
+use crate::repo::{GitRepo, RepoKind};
+use crate::shell::tmux::{Resolved, Session, SessionInfo, SessionName, list_sessions, resolve};
 use crate::ui::picker::Picker;
 use crate::ui::ratinit::{self, DefaultTerminal};
 
+mod preview;
 mod ui;
 
+/// Which entries the picker lists
 #[derive(Clone, ValueEnum)]
 pub enum Mode {
-    Repos,
+    /// Every repo, and sessions outside of them
+    All,
+    /// Entries with a live session
     Sessions,
+    Repos,
+}
+
+impl Mode {
+    fn next(&self) -> Self {
+        match self {
+            Mode::All => Mode::Sessions,
+            Mode::Sessions => Mode::Repos,
+            Mode::Repos => Mode::All,
+        }
+    }
+
+    fn shows(&self, entry: &Entry) -> bool {
+        match (self, entry) {
+            (Mode::All, _) => true,
+            (Mode::Sessions, entry) => entry.session().is_some(),
+            (Mode::Repos, Entry::Repo(..)) => true,
+            (Mode::Repos, Entry::Session(_)) => false,
+        }
+    }
+}
+
+/// A row of the picker
+#[derive(Clone)]
+enum Entry {
+    /// A repo, with the session started in it if there is one
+    Repo(GitRepo, Option<SessionInfo>),
+    /// A session that wasn't started in any repo
+    Session(SessionInfo),
+}
+
+impl Entry {
+    fn session(&self) -> Option<&SessionInfo> {
+        match self {
+            Entry::Repo(_, session) => session.as_ref(),
+            Entry::Session(session) => Some(session),
+        }
+    }
+
+    /// The text searched for the entry, its repo's path or its session's name
+    fn format(&self, root: &Path) -> Utf32String {
+        match self {
+            Entry::Repo(repo, _) => repo.relative_path(root).to_string_lossy().as_ref().into(),
+            Entry::Session(session) => session.session.name_str().into(),
+        }
+    }
+
+    /// Shown after the entry without being searched, so searching for `session` doesn't match
+    /// every session
+    fn tag(&self) -> String {
+        let mut tag = match self {
+            Entry::Repo(repo, _) if repo.kind() != RepoKind::Normal => {
+                format!(" ({})", repo.kind().as_str())
+            }
+            _ => String::new(),
+        };
+
+        match self.session() {
+            Some(session) if session.attached => tag.push_str(" [attached]"),
+            Some(_) => tag.push_str(" [session]"),
+            None => (),
+        }
+
+        tag
+    }
+}
+
+/// Sent by the discovery thread as it finds repos
+enum Progress {
+    Found(GitRepo),
+    /// Repos disappeared, so the picker has to start over with the remaining ones
+    Rebuild(Vec<GitRepo>),
+    Done,
+}
+
+/// An action on the highlighted session, confirmed in place of the search
+enum Action {
+    Kill(Session),
+    Rename { session: Session, name: String },
+    DetachOthers(Session),
 }
 
 enum Status {
@@ -25,54 +121,186 @@ enum Status {
 
 struct App {
     mode: Mode,
-    repo_picker: Picker<GitRepo, PathBuf>,
-    session_picker: Picker<Session, ()>,
+    /// When discovery started, while it's still running
+    discovering: Option<Instant>,
+    repos: Vec<GitRepo>,
+    sessions: Vec<SessionInfo>,
+    /// Whether a repo found since the last rebuild claimed a session that's listed on its own
+    claimed: bool,
+    picker: Picker<Entry, PathBuf>,
     search: String,
     status: Status,
+    /// Whether the preview pane is shown next to the picker
+    preview: bool,
+    previews: Previews,
+    /// A session action waiting for confirmation
+    action: Option<Action>,
+    /// Why the last session action failed, shown in place of the search until the next key
+    error: Option<String>,
 }
 
 impl App {
-    pub fn new(mode: Mode, root: &Path) -> Self {
+    pub fn new(
+        mode: Mode,
+        root: &Path,
+        repos: Frecency,
+        sessions: Frecency,
+        preview: bool,
+    ) -> Self {
+        let now = frecency::now();
+
         Self {
             mode,
-            repo_picker: Picker::new(
-                |repo_ref, data| repo_ref.relative_path(data).to_string_lossy().into(),
+            discovering: Some(Instant::now()),
+            repos: vec![],
+            sessions: vec![],
+            claimed: false,
+            picker: Picker::new(
+                |entry: &Entry, root: &PathBuf| entry.format(root),
                 root.to_owned(),
-            ),
-            session_picker: Picker::new(|session, _| session.name_str().into(), ()),
+            )
+            .with_bias(move |entry| match entry {
+                Entry::Repo(repo, _) => repos.score(&repo.path().to_string_lossy(), now),
+                Entry::Session(session) => sessions.score(session.session.name_str(), now),
+            })
+            .with_tag(Entry::tag),
             search: String::new(),
             status: Status::Running,
+            preview,
+            previews: Previews::new(),
+            action: None,
+            error: None,
+        }
+    }
+
+    fn receive(&mut self, progress: &Receiver<Progress>) {
+        loop {
+            match progress.try_recv() {
+                Ok(Progress::Found(repo)) => self.add(repo),
+                Ok(Progress::Rebuild(repos)) => {
+                    self.repos = repos;
+                    self.rebuild();
+                }
+                Ok(Progress::Done) | Err(TryRecvError::Disconnected) => {
+                    self.done();
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+            }
+        }
+    }
+
+    /// Blocks until discovery is done, for decisions that need every repo
+    fn finish_discovery(&mut self, progress: &Receiver<Progress>) {
+        while self.discovering.is_some() {
+            match progress.recv() {
+                Ok(Progress::Found(repo)) => self.add(repo),
+                Ok(Progress::Rebuild(repos)) => {
+                    self.repos = repos;
+                    self.rebuild();
+                }
+                Ok(Progress::Done) | Err(_) => self.done(),
+            }
+        }
+    }
+
+    /// Lists a found repo, with the session started in it if there is one
+    fn add(&mut self, repo: GitRepo) {
+        let session = self
+            .sessions
+            .iter()
+            .find(|session| session.path == repo.path())
+            .cloned();
+
+        // the session may be listed on its own so far, which is only undone once discovery is
+        // done, to not reset the selection for every repo found
+        self.claimed |= session.is_some();
+
+        let entry = Entry::Repo(repo.clone(), session);
+        if self.mode.shows(&entry) {
+            self.picker.push(entry);
+        }
+
+        self.repos.push(repo);
+    }
+
+    fn done(&mut self) {
+        self.discovering = None;
+
+        if self.claimed {
+            self.rebuild();
+        }
+    }
+
+    /// Lists every repo and session again, for a changed mode or sessions
+    fn rebuild(&mut self) {
+        self.picker.clear();
+        self.claimed = false;
+
+        let mut unclaimed: Vec<&SessionInfo> = self.sessions.iter().collect();
+
+        for repo in &self.repos {
+            let session = unclaimed
+                .iter()
+                .position(|session| session.path == repo.path())
+                .map(|i| unclaimed.remove(i).clone());
+
+            let entry = Entry::Repo(repo.clone(), session);
+            if self.mode.shows(&entry) {
+                self.picker.push(entry);
+            }
+        }
+
+        for session in unclaimed {
+            let entry = Entry::Session(session.clone());
+            if self.mode.shows(&entry) {
+                self.picker.push(entry);
+            }
+        }
+    }
+
+    fn set_query(&mut self, query: &str) {
+        self.search = query.into();
+        self.picker.push_char(&self.search);
+    }
+
+    /// Completes or fails without the UI, if the options call for it with the current matches
+    ///
+    /// Returns whether the selection was decided.
+    fn decide(&mut self, options: &Options, progress: &Receiver<Progress>) -> anyhow::Result<bool> {
+        if !options.select_1 && !options.exit_0 {
+            return Ok(false);
+        }
+
+        self.finish_discovery(progress);
+        self.picker.settle();
+
+        match self.picker.counts().0 {
+            0 if options.exit_0 => bail!("Nothing matches {:?}", self.search),
+            1 if options.select_1 => {
+                self.complete();
+                Ok(true)
+            }
+            _ => Ok(false),
         }
     }
 
     pub fn pop_char(&mut self) {
         self.search.pop();
-        match &self.mode {
-            Mode::Repos => &self.repo_picker.pop_char(&self.search),
-            Mode::Sessions => &self.session_picker.pop_char(&self.search),
-        };
+        self.picker.pop_char(&self.search);
     }
 
     pub fn push_char(&mut self, c: char) {
         self.search.push(c);
-        match &self.mode {
-            Mode::Repos => &self.repo_picker.push_char(&self.search),
-            Mode::Sessions => &self.session_picker.push_char(&self.search),
-        };
+        self.picker.push_char(&self.search);
     }
 
     fn dec_selection(&mut self) {
-        match &self.mode {
-            Mode::Repos => self.repo_picker.dec_selection(),
-            Mode::Sessions => self.session_picker.dec_selection(),
-        };
+        self.picker.dec_selection();
     }
 
     fn inc_selection(&mut self) {
-        match &self.mode {
-            Mode::Repos => self.repo_picker.inc_selection(),
-            Mode::Sessions => self.session_picker.inc_selection(),
-        };
+        self.picker.inc_selection();
     }
 
     pub fn abort(&mut self) {
@@ -88,60 +316,97 @@ impl App {
     }
 
     fn toggle_mode(&mut self) {
-        match self.mode {
-            Mode::Repos => {
-                self.mode = Mode::Sessions;
-                self.session_picker.pop_char(&self.search);
-                self.session_picker.tick();
-                self.session_picker.select(self.repo_picker.selected());
-            }
-            Mode::Sessions => {
-                self.mode = Mode::Repos;
-                self.repo_picker.pop_char(&self.search);
-                self.repo_picker.tick();
-                self.repo_picker.select(self.session_picker.selected());
-            }
-        }
+        self.mode = self.mode.next();
+        self.rebuild();
     }
 
-    pub fn run(&mut self, terminal: &mut DefaultTerminal, root: &Path) -> anyhow::Result<()> {
-        {
-            let mut it = WalkDir::new(root).into_iter();
+    fn reload_sessions(&mut self) -> anyhow::Result<()> {
+        self.sessions = list_sessions()?;
+        self.previews.forget_sessions();
+        self.rebuild();
 
-            while let Some(Ok(entry)) = it.next() {
-                if entry.path().join(".git").read_dir().is_ok() {
-                    it.skip_current_dir();
+        Ok(())
+    }
 
-                    if let Some(name) = entry.file_name().to_str() {
-                        let repo = GitRepo::new(name.into(), entry.path().into());
+    /// Asks to confirm an action on the highlighted entry's session, if it has one
+    fn propose(&mut self, action: fn(Session) -> Action) {
+        if let Some(entry) = self.picker.selected_data()
+            && let Some(info) = entry.session()
+        {
+            self.action = Some(action(info.session.clone()));
+        }
+    }
 
-                        self.repo_picker.push(repo);
-                    }
-                }
+    /// Handles a key while an action waits for confirmation, performing or dropping it
+    ///
+    /// A failed action is shown instead of ending the picker.
+    fn confirm(&mut self, action: Action, key: KeyCode) {
+        let performed = match (action, key) {
+            (Action::Kill(session), KeyCode::Char('y')) => session.kill(),
+            (Action::DetachOthers(session), KeyCode::Char('y')) => session.detach_other_clients(),
+            (Action::Rename { session, name }, KeyCode::Enter) if !name.is_empty() => {
+                session.rename(name.as_str()).map(|_| ())
+            }
+            (Action::Rename { session, mut name }, KeyCode::Char(c)) => {
+                name.push(c);
+                self.action = Some(Action::Rename { session, name });
+                return;
             }
+            (Action::Rename { session, mut name }, KeyCode::Backspace) => {
+                name.pop();
+                self.action = Some(Action::Rename { session, name });
+                return;
+            }
+            _ => return,
         };
 
-        for session in list_sessions()? {
-            self.session_picker.push(session);
+        if let Err(e) = performed
+            .map_err(anyhow::Error::from)
+            .and_then(|()| self.reload_sessions())
+        {
+            self.error = Some(format!("{e:#}"));
         }
+    }
 
+    pub fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        progress: &Receiver<Progress>,
+    ) -> anyhow::Result<()> {
         while self.is_running() {
+            self.receive(progress);
+
             terminal.draw(|frame| ui::render(self, frame))?;
 
-            if event::poll(std::time::Duration::from_millis(16))? {
-                if let event::Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Esc => self.abort(),
-                            KeyCode::Char(key) => self.push_char(key),
-                            KeyCode::Backspace => self.pop_char(),
-                            KeyCode::Up => self.inc_selection(),
-                            KeyCode::Down => self.dec_selection(),
-                            KeyCode::Enter => self.complete(),
-                            KeyCode::Tab => self.toggle_mode(),
-                            _ => (),
-                        }
-                    }
+            if event::poll(std::time::Duration::from_millis(16))?
+                && let event::Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.error = None;
+
+                if let Some(action) = self.action.take() {
+                    self.confirm(action, key.code);
+                    continue;
+                }
+
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+                match key.code {
+                    KeyCode::Char('p') if ctrl => self.preview = !self.preview,
+                    KeyCode::Char('x') if ctrl => self.propose(Action::Kill),
+                    KeyCode::Char('r') if ctrl => self.propose(|session| Action::Rename {
+                        name: session.name_str().into(),
+                        session,
+                    }),
+                    KeyCode::Char('d') if ctrl => self.propose(Action::DetachOthers),
+                    KeyCode::Esc => self.abort(),
+                    KeyCode::Char(key) => self.push_char(key),
+                    KeyCode::Backspace => self.pop_char(),
+                    KeyCode::Up => self.inc_selection(),
+                    KeyCode::Down => self.dec_selection(),
+                    KeyCode::Enter => self.complete(),
+                    KeyCode::Tab => self.toggle_mode(),
+                    _ => (),
                 }
             }
         }
@@ -150,33 +415,250 @@ impl App {
     }
 }
 
-pub fn run(mode: &Mode, config: crate::Config) -> anyhow::Result<()> {
-    let mut terminal = ratinit::init();
+/// fzf-style options for picking without the UI
+pub struct Options {
+    pub query: Option<String>,
+    /// Pick the only match without showing the UI
+    pub select_1: bool,
+    /// Fail without showing the UI when nothing matches
+    pub exit_0: bool,
+}
+
+pub fn run(mode: &Mode, options: &Options, config: Config) -> anyhow::Result<()> {
+    let mut app = App::new(
+        mode.clone(),
+        &config.root,
+        Frecency::repos()?,
+        Frecency::sessions()?,
+        config.preview,
+    );
 
-    let mut app = App::new(mode.clone(), &config.root);
-    let app_result = app.run(&mut terminal, &config.root);
+    let (progress_tx, progress_rx) = mpsc::channel();
+    let rules = Discovery::from_config(&config)?;
+
+    // discovery isn't waited for once the picker closes: attaching blocks until the client
+    // detaches, which is long enough for it to save the index, and a refresh cut short by
+    // switching clients is done again next time
+    thread::spawn(move || discover(rules, &progress_tx));
+
+    pick(&mut app, options, &progress_rx).and_then(|_| attach(&app, &config))
+}
+
+fn pick(app: &mut App, options: &Options, progress: &Receiver<Progress>) -> anyhow::Result<()> {
+    app.reload_sessions()?;
+
+    if let Some(query) = &options.query {
+        app.set_query(query);
+    }
+
+    if app.decide(options, progress)? {
+        return Ok(());
+    }
+
+    let mut terminal = ratinit::init();
+    let result = app.run(&mut terminal, progress);
 
     ratinit::restore();
 
-    // Ensure the terminal is reset before possibly returning early
-    app_result?;
+    result
+}
+
+/// Sends the indexed repos, then whatever a refresh of the index finds
+fn discover(rules: Discovery, progress: &Sender<Progress>) -> io::Result<()> {
+    let mut index = Index::load(rules)?;
+    let mut removed = vec![];
+
+    for repo in index.repos() {
+        let _ = progress.send(Progress::Found(repo));
+    }
+
+    index.refresh(|change| match change {
+        Change::Added(repo) => {
+            let _ = progress.send(Progress::Found(repo));
+        }
+        Change::Removed(path) => removed.push(path),
+    });
+
+    if !removed.is_empty() {
+        let _ = progress.send(Progress::Rebuild(index.repos().collect()));
+    }
+
+    let _ = progress.send(Progress::Done);
+
+    index.save()
+}
 
+fn attach(app: &App, config: &Config) -> anyhow::Result<()> {
     if let Status::Aborted = app.status {
         return Ok(());
     };
 
-    match &app.mode {
-        Mode::Repos => {
-            let Some(repo) = app.repo_picker.selected_data() else {
-                return Ok(());
-            };
-            Ok(Session::find_or_create_in(repo.name(), repo.path())?.attach_or_switch()?)
-        }
-        Mode::Sessions => {
-            let Some(session) = app.session_picker.selected_data() else {
-                return Ok(());
+    match app.picker.selected_data() {
+        Some(Entry::Repo(repo, session)) => {
+            record(Frecency::repos()?, &repo.path().to_string_lossy())?;
+
+            let session = match session {
+                Some(info) => info.session,
+                None => {
+                    let name = config.session_naming.name(repo.relative_path(&config.root));
+
+                    find_or_create(name, repo.path(), config)?
+                }
             };
+
             Ok(session.attach_or_switch()?)
         }
+        Some(Entry::Session(info)) => {
+            record(Frecency::sessions()?, info.session.name_str())?;
+            Ok(info.session.attach_or_switch()?)
+        }
+        None => Ok(()),
+    }
+}
+
+fn record(mut frecency: Frecency, key: &str) -> io::Result<()> {
+    frecency.record(key, frecency::now());
+    frecency.save()
+}
+
+/// Finds the session started in a repository, or starts one with the windows of its trusted
+/// recipe or the config
+///
+/// A new session is named `name` unless another repository's session took it first, then after
+/// more of the repository's path.
+pub fn find_or_create(name: SessionName, path: &Path, config: &Config) -> anyhow::Result<Session> {
+    let relative_path = path.strip_prefix(&config.root).unwrap_or(path);
+
+    let name = match resolve(name, path, relative_path, &list_sessions()?)? {
+        Resolved::Existing(session) => return Ok(session),
+        Resolved::New(name) => name,
+    };
+
+    let windows = match Recipe::load(path)? {
+        Some(recipe) if !recipe.windows().is_empty() && ensure_trusted(path, &recipe)? => {
+            recipe.windows().to_vec()
+        }
+        _ => config.windows.clone(),
+    };
+
+    Session::create_in(name, path, &windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_tagged_with_their_session() {
+        let root = Path::new("/src");
+        let repo = GitRepo::at(Path::new("/src/github.com/rails/rails"), RepoKind::Normal).unwrap();
+        let live = SessionInfo::started("rails", "/src/github.com/rails/rails", true);
+
+        let notes = Entry::Session(SessionInfo::started("notes", "/tmp", false));
+        let attached = Entry::Repo(repo.clone(), Some(live));
+
+        assert_eq!("", Entry::Repo(repo, None).tag());
+        assert_eq!("github.com/rails/rails", attached.format(root).to_string());
+        assert_eq!(" [attached]", attached.tag());
+        assert_eq!("notes", notes.format(root).to_string());
+        assert_eq!(" [session]", notes.tag());
+    }
+
+    fn app() -> App {
+        let tmp = tempfile::tempdir().unwrap();
+        let frecency = || Frecency::load(tmp.path().join("frecency")).unwrap();
+
+        App::new(Mode::All, Path::new("/src"), frecency(), frecency(), false)
+    }
+
+    fn renaming(app: &App) -> Option<&str> {
+        match &app.action {
+            Some(Action::Rename { name, .. }) => Some(name),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn rename_is_typed_until_enter() {
+        let mut app = app();
+        let rename = |name: &str| Action::Rename {
+            session: Session::from("notes"),
+            name: name.into(),
+        };
+
+        app.confirm(rename("note"), KeyCode::Char('s'));
+        assert_eq!(Some("notes"), renaming(&app));
+
+        let action = app.action.take().unwrap();
+        app.confirm(action, KeyCode::Backspace);
+        assert_eq!(Some("note"), renaming(&app));
+
+        // an empty name is dropped instead of renaming
+        app.action = None;
+        app.confirm(rename(""), KeyCode::Enter);
+        assert!(app.action.is_none() && app.error.is_none());
+    }
+
+    #[test]
+    fn actions_are_dropped_without_y() {
+        let mut app = app();
+
+        app.confirm(Action::Kill(Session::from("notes")), KeyCode::Char('n'));
+        app.confirm(Action::DetachOthers(Session::from("notes")), KeyCode::Enter);
+
+        assert!(app.action.is_none() && app.error.is_none());
+    }
+
+    #[test]
+    fn failed_actions_are_shown() {
+        let mut app = app();
+        let missing = format!("sdev-missing-{}", std::process::id());
+
+        app.confirm(
+            Action::Kill(Session::from(missing.as_str())),
+            KeyCode::Char('y'),
+        );
+
+        assert!(app.action.is_none());
+        assert!(app.error.is_some());
+    }
+
+    #[test]
+    fn sessions_are_claimed_by_found_repos_once_discovery_is_done() {
+        let mut app = app();
+        app.sessions = vec![SessionInfo::started(
+            "rails",
+            "/src/github.com/rails/rails",
+            false,
+        )];
+        app.rebuild();
+
+        let repo = GitRepo::at(Path::new("/src/github.com/rails/rails"), RepoKind::Normal).unwrap();
+        app.add(repo);
+        app.picker.settle();
+
+        // the repo is listed with its session right away, next to the session on its own
+        assert_eq!(2, app.picker.counts().0);
+        assert!(app.claimed);
+
+        app.done();
+        app.picker.settle();
+
+        assert_eq!(1, app.picker.counts().0);
+        assert!(matches!(
+            app.picker.selected_data(),
+            Some(Entry::Repo(_, Some(_)))
+        ));
+    }
+
+    #[test]
+    fn modes_filter_entries() {
+        let repo = GitRepo::at(Path::new("/src/github.com/rails/rails"), RepoKind::Normal).unwrap();
+        let unstarted = Entry::Repo(repo, None);
+        let orphan = Entry::Session(SessionInfo::started("notes", "/tmp", false));
+
+        assert!(Mode::All.shows(&unstarted) && Mode::All.shows(&orphan));
+        assert!(!Mode::Sessions.shows(&unstarted) && Mode::Sessions.shows(&orphan));
+        assert!(Mode::Repos.shows(&unstarted) && !Mode::Repos.shows(&orphan));
     }
 }
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gix::bstr::ByteSlice;
+use gix::remote::Direction;
+use ratatui_core::{style::Stylize, text::Line};
+
+use crate::frecency;
+use crate::shell::tmux::Session;
+
+const README_LINES: usize = 20;
+/// How long a preview is shown before it's described again, for a status that changes while
+/// the picker is open
+const MAX_AGE: Duration = Duration::from_secs(5);
+
+/// What the preview pane describes
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+    Repo(PathBuf),
+    Session(Session),
+}
+
+type Preview = Vec<Line<'static>>;
+
+/// Previews of picker entries, described on a background thread so a slow status doesn't block
+/// typing
+pub struct Previews {
+    requests: Sender<Target>,
+    results: Receiver<(Target, Preview)>,
+    /// The last preview of each target, `None` until it's first described, and when it was last
+    /// requested
+    cache: HashMap<Target, (Option<Preview>, Instant)>,
+}
+
+impl Previews {
+    pub fn new() -> Self {
+        let (requests, pending) = mpsc::channel::<Target>();
+        let (described, results) = mpsc::channel();
+
+        // stops once the picker drops its sender
+        thread::spawn(move || {
+            for target in pending {
+                let preview = describe(&target);
+
+                if described.send((target, preview)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            requests,
+            results,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// The preview of `target`, requesting it the first time it's asked for and again once the
+    /// last one is out of date
+    pub fn get(&mut self, target: Target) -> Option<&[Line<'static>]> {
+        for (target, preview) in self.results.try_iter() {
+            if let Some((cached, _)) = self.cache.get_mut(&target) {
+                *cached = Some(preview);
+            }
+        }
+
+        let stale = self
+            .cache
+            .get(&target)
+            .is_none_or(|(_, requested)| requested.elapsed() > MAX_AGE);
+
+        if stale {
+            let _ = self.requests.send(target.clone());
+
+            let (_, requested) = self
+                .cache
+                .entry(target.clone())
+                .or_insert((None, Instant::now()));
+            *requested = Instant::now();
+        }
+
+        self.cache.get(&target)?.0.as_deref()
+    }
+
+    /// Drops every session's preview, after sessions were changed
+    pub fn forget_sessions(&mut self) {
+        self.cache
+            .retain(|target, _| !matches!(target, Target::Session(_)));
+    }
+}
+
+fn describe(target: &Target) -> Preview {
+    let preview = match target {
+        Target::Repo(path) => repo(path),
+        Target::Session(session) => self::session(session),
+    };
+
+    preview.unwrap_or_else(|e| vec![Line::from(e.to_string().red())])
+}
+
+/// The current branch, how it compares to its upstream, the last commit and the README
+fn repo(path: &Path) -> anyhow::Result<Preview> {
+    let repo = gix::open(path)?;
+    let mut lines = vec![];
+
+    let Ok(commit) = repo.head_commit() else {
+        let branch = repo.head_name()?.map(|name| name.shorten().to_string());
+        lines.push(Line::from(vec![
+            branch.unwrap_or_default().bold(),
+            " no commits yet".dark_gray(),
+        ]));
+
+        return Ok(lines);
+    };
+
+    let mut status = match repo.head_name()? {
+        Some(name) => {
+            let mut spans = vec![name.shorten().to_string().bold()];
+
+            if let Some((ahead, behind)) = ahead_behind(&repo, &name, commit.id) {
+                spans.push(format!(" ↑{ahead} ↓{behind}").cyan());
+            }
+
+            spans
+        }
+        None => vec![format!("detached at {}", commit.id().shorten_or_id()).bold()],
+    };
+
+    // bare repositories have no working tree to be dirty
+    if repo.workdir().is_some() {
+        status.push(match repo.is_dirty()? {
+            true => " dirty".yellow(),
+            false => " clean".green(),
+        });
+    }
+
+    lines.push(Line::from(status));
+
+    let summary = commit.message()?.summary().to_str_lossy().into_owned();
+    let age = ago(commit.time()?.seconds, frecency::now());
+
+    lines.push(Line::from(vec![
+        format!("{} ", commit.id().shorten_or_id()).yellow(),
+        summary.into(),
+        format!(" ({age})").dark_gray(),
+    ]));
+
+    if let Some(readme) = readme(&repo, &commit)? {
+        lines.push(Line::default());
+        lines.extend(
+            readme
+                .lines()
+                .take(README_LINES)
+                .map(|line| Line::from(line.to_string())),
+        );
+    }
+
+    Ok(lines)
+}
+
+/// How many commits `head` has that its upstream doesn't, and the other way around
+fn ahead_behind(
+    repo: &gix::Repository,
+    name: &gix::refs::FullName,
+    head: gix::ObjectId,
+) -> Option<(usize, usize)> {
+    let upstream = repo
+        .branch_remote_tracking_ref_name(name.as_ref(), Direction::Fetch)?
+        .ok()?;
+    let upstream = repo
+        .find_reference(upstream.as_ref())
+        .ok()?
+        .into_fully_peeled_id()
+        .ok()?
+        .detach();
+
+    let count = |tip: gix::ObjectId, hidden: gix::ObjectId| {
+        let walk = repo.rev_walk([tip]).with_hidden([hidden]).all().ok()?;
+
+        Some(walk.count())
+    };
+
+    Some((count(head, upstream)?, count(upstream, head)?))
+}
+
+/// The README committed at the root of `commit`, read from git so bare repositories have one too
+fn readme(repo: &gix::Repository, commit: &gix::Commit<'_>) -> anyhow::Result<Option<String>> {
+    let tree = commit.tree()?;
+
+    for entry in tree.iter() {
+        let entry = entry?;
+
+        if entry.mode().is_blob()
+            && entry
+                .filename()
+                .to_str_lossy()
+                .to_lowercase()
+                .starts_with("readme")
+        {
+            let blob = repo.find_blob(entry.oid())?;
+
+            return Ok(Some(blob.data.to_str_lossy().into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The windows of a session and the commands running in their panes
+fn session(session: &Session) -> anyhow::Result<Preview> {
+    let lines = session
+        .windows()?
+        .into_iter()
+        .map(|window| {
+            let name = format!("{}: {}", window.index, window.name);
+
+            Line::from(vec![
+                if window.active {
+                    name.bold()
+                } else {
+                    name.into()
+                },
+                format!(" {}", window.commands.join(", ")).dark_gray(),
+            ])
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+fn ago(seconds: i64, now: u64) -> String {
+    let elapsed = u64::try_from(seconds).map_or(0, |seconds| now.saturating_sub(seconds));
+
+    let (count, unit) = match elapsed {
+        0..60 => return "just now".into(),
+        60..3600 => (elapsed / 60, "minute"),
+        3600..86400 => (elapsed / 3600, "hour"),
+        86400..2592000 => (elapsed / 86400, "day"),
+        2592000..31536000 => (elapsed / 2592000, "month"),
+        _ => (elapsed / 31536000, "year"),
+    };
+
+    match count {
+        1 => format!("1 {unit} ago"),
+        _ => format!("{count} {unit}s ago"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn ages_are_rounded_down_to_the_largest_unit() {
+        let now = 1_000_000_000;
+
+        assert_eq!("just now", ago(now - 59, now as u64));
+        assert_eq!("1 minute ago", ago(now - 119, now as u64));
+        assert_eq!("3 hours ago", ago(now - 3 * 3600, now as u64));
+        assert_eq!("2 years ago", ago(now - 2 * 31536000, now as u64));
+        assert_eq!("just now", ago(now + 10, now as u64));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=sdev", "-c", "user.email=sdev@example.com"])
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn repos_are_previewed_with_their_readme() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path();
+        fs::write(path.join("README.md"), "# demo\n").unwrap();
+
+        git(path, &["init", "-q", "-b", "main"]);
+        git(path, &["add", "README.md"]);
+        git(path, &["commit", "-q", "-m", "Add readme"]);
+
+        let lines: Vec<String> = repo(path)
+            .unwrap()
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+
+        assert_eq!("main clean", lines[0]);
+        assert!(lines[1].ends_with("Add readme (just now)"));
+        assert_eq!(vec!["", "# demo"], lines[2..]);
+    }
+
+    /// Asks for a preview until the background thread has described it
+    fn wait_for(previews: &mut Previews, target: &Target) -> String {
+        for _ in 0..500 {
+            if let Some(lines) = previews.get(target.clone()) {
+                return lines[0].to_string();
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        panic!("preview never arrived")
+    }
+
+    #[test]
+    fn previews_are_described_again_once_stale() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path();
+        fs::write(path.join("README.md"), "# demo\n").unwrap();
+
+        git(path, &["init", "-q", "-b", "main"]);
+        git(path, &["add", "README.md"]);
+        git(path, &["commit", "-q", "-m", "Add readme"]);
+
+        let mut previews = Previews::new();
+        let target = Target::Repo(path.into());
+
+        assert_eq!("main clean", wait_for(&mut previews, &target));
+
+        fs::write(path.join("README.md"), "# changed\n").unwrap();
+        assert_eq!("main clean", wait_for(&mut previews, &target));
+
+        previews.cache.get_mut(&target).unwrap().1 -= MAX_AGE;
+        previews.get(target.clone());
+        previews.cache.get_mut(&target).unwrap().0 = None;
+
+        assert_eq!("main dirty", wait_for(&mut previews, &target));
+    }
+
+    #[test]
+    fn forgotten_sessions_are_described_again() {
+        let mut previews = Previews::new();
+        let session = Target::Session(Session::from("sdev-missing-session"));
+        let repo = Target::Repo("/missing".into());
+
+        previews.get(session.clone());
+        previews.get(repo.clone());
+        previews.forget_sessions();
+
+        assert!(!previews.cache.contains_key(&session));
+        assert!(previews.cache.contains_key(&repo));
+    }
+}
@@ -1,33 +1,114 @@
 use ratatui_core::{
     layout::{Constraint, Layout},
-    style::Stylize,
+    style::{Style, Stylize},
     terminal::Frame,
     text::Line,
 };
+use ratatui_widgets::{
+    block::Block,
+    borders::Borders,
+    paragraph::{Paragraph, Wrap},
+};
 
-use crate::cmd::tmux::{App, Mode};
+use crate::cmd::tmux::preview::Target;
+use crate::cmd::tmux::{Action, App, Entry, Mode};
 
 const PADDED_CHEVRON: &str = "> ";
+/// Narrower terminals only show the picker
+const MIN_PREVIEW_WIDTH: u16 = 80;
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 pub fn render(app: &mut App, frame: &mut Frame) {
     let layout =
         Layout::vertical([Constraint::Percentage(100), Constraint::Min(1)]).split(frame.area());
 
-    let prompt = Line::from(vec![
-        PADDED_CHEVRON.bold().magenta(),
-        app.search.clone().bold(),
-    ]);
+    let (prompt, input) = match &app.action {
+        Some(Action::Kill(session)) => (
+            Line::from(format!("Kill session {}? [y/N]", session.name_str()).red()),
+            None,
+        ),
+        Some(Action::DetachOthers(session)) => (
+            Line::from(format!(
+                "Detach other clients from {}? [y/N]",
+                session.name_str()
+            )),
+            None,
+        ),
+        Some(Action::Rename { session, name }) => {
+            let label = format!("Rename {} to ", session.name_str());
+            let cursor = label.len() + name.len();
 
-    match &app.mode {
-        Mode::Repos => app.repo_picker.render(layout[0], frame.buffer_mut()),
-        Mode::Sessions => app.session_picker.render(layout[0], frame.buffer_mut()),
+            (
+                Line::from(vec![label.into(), name.clone().bold()]),
+                Some(cursor),
+            )
+        }
+        None => match &app.error {
+            Some(error) => (Line::from(error.clone().red()), None),
+            None => (
+                Line::from(vec![
+                    PADDED_CHEVRON.bold().magenta(),
+                    app.search.clone().bold(),
+                ]),
+                Some(2 + app.search.len()),
+            ),
+        },
+    };
+
+    let (picker_area, preview_area) = if app.preview && frame.area().width >= MIN_PREVIEW_WIDTH {
+        let columns = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(layout[0]);
+
+        (columns[0], Some(columns[1]))
+    } else {
+        (layout[0], None)
     };
 
+    app.picker.render(picker_area, frame.buffer_mut());
+
+    let (matched, total) = app.picker.counts();
+    let target = app.picker.selected_data().map(|entry| match entry {
+        Entry::Repo(repo, _) => Target::Repo(repo.path().into()),
+        Entry::Session(info) => Target::Session(info.session),
+    });
+
+    if let (Some(area), Some(target)) = (preview_area, target) {
+        let preview = match app.previews.get(target) {
+            Some(lines) => Paragraph::new(lines.to_vec()),
+            None => Paragraph::new("…".dark_gray()),
+        };
+        let block = Block::default()
+            .borders(Borders::LEFT)
+            .border_style(Style::new().dark_gray());
+
+        frame.render_widget(preview.block(block).wrap(Wrap { trim: false }), area);
+    }
+
+    // styled per span, since a styled line would restyle the prompt it's drawn over
+    let mut counts = Line::from(format!("{matched}/{total}").dark_gray());
+
+    match &app.mode {
+        Mode::All => (),
+        Mode::Sessions => counts.spans.insert(0, "sessions ".cyan()),
+        Mode::Repos => counts.spans.insert(0, "repos ".cyan()),
+    }
+
+    if let Some(started) = app.discovering {
+        let frame = started.elapsed().as_millis() / 80 % SPINNER.len() as u128;
+
+        counts
+            .spans
+            .insert(0, format!("{} ", SPINNER[frame as usize]).magenta());
+    }
+
     frame.render_widget(prompt, layout[1]);
 
+    if app.action.is_none() && app.error.is_none() {
+        frame.render_widget(counts.right_aligned(), layout[1]);
+    }
+
     // TODO: unwrap because the string length should not exceed u16
-    frame.set_cursor_position((
-        (2 + app.search.len()).try_into().unwrap(),
-        frame.area().height,
-    ));
+    if let Some(cursor) = input {
+        frame.set_cursor_position((cursor.try_into().unwrap(), frame.area().height));
+    }
 }
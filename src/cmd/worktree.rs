@@ -0,0 +1,158 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail};
+
+use crate::Config;
+use crate::cmd::tmux;
+use crate::dep::executor::Executor;
+use crate::dep::git::{BARE_DIR, Worktree, has_worktree, linked_worktrees};
+use crate::dep::plan::Plan;
+use crate::dep::report::Report;
+use crate::shell;
+use crate::ui::select::select;
+
+/// The repository around the current directory, which may be one of its worktrees
+struct Repo {
+    repo: gix::Repository,
+    /// The clone's directory, holding `.git` or `.bare`
+    root: PathBuf,
+}
+
+impl Repo {
+    fn discover() -> anyhow::Result<Self> {
+        let repo = gix::discover(env::current_dir()?)?;
+        let root = repo
+            .common_dir()
+            .parent()
+            .ok_or_else(|| anyhow!("{} has no parent", repo.common_dir().display()))?
+            .to_owned();
+
+        Ok(Self { repo, root })
+    }
+
+    fn is_bare_layout(&self) -> bool {
+        self.repo
+            .common_dir()
+            .file_name()
+            .is_some_and(|name| name == BARE_DIR)
+    }
+
+    fn worktree_path(&self, branch: &str) -> PathBuf {
+        path_for(&self.root, self.is_bare_layout(), branch)
+    }
+
+    /// Every checked out worktree, starting with the clone itself unless it's bare
+    fn worktrees(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let main = (!self.is_bare_layout()).then(|| self.root.clone());
+
+        Ok(main
+            .into_iter()
+            .chain(linked_worktrees(&self.repo)?)
+            .collect())
+    }
+
+    /// How a worktree is shown and its tmux session named, like `sdev@feature` or `sdev/main`
+    fn display_name(&self, worktree: &Path) -> String {
+        let parent = self.root.parent().unwrap_or(&self.root);
+
+        worktree
+            .strip_prefix(parent)
+            .unwrap_or(worktree)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Where the worktree for `branch` lives: inside a clone with the worktrees layout, or next to a
+/// regular clone as `<repo>@<branch>`
+fn path_for(root: &Path, bare_layout: bool, branch: &str) -> PathBuf {
+    if bare_layout {
+        return root.join(branch);
+    }
+
+    let name = root.file_name().unwrap_or_default().to_string_lossy();
+
+    root.with_file_name(format!("{name}@{}", branch.replace('/', "-")))
+}
+
+pub fn add(branch: &str, dry_run: bool) -> anyhow::Result<()> {
+    let repo = Repo::discover()?;
+    let worktree = Worktree::new(repo.root.clone(), repo.worktree_path(branch), branch.into());
+
+    if dry_run {
+        print!("{}", Plan::new(&worktree));
+        return Ok(());
+    }
+
+    let report = Report::default();
+    let result = Executor::default().with_reporter(&report).run(&worktree);
+
+    print!("{report}");
+
+    result
+}
+
+pub fn rm(branch: &str, dry_run: bool) -> anyhow::Result<()> {
+    let repo = Repo::discover()?;
+    let path = repo.worktree_path(branch);
+
+    if !has_worktree(&repo.repo, &path, branch)? {
+        bail!("no worktree for {branch} at {}", path.display())
+    }
+
+    let mut git = shell::new!("git", "worktree", "remove", &path).in_dir(&repo.root);
+
+    if dry_run {
+        println!("{git}");
+        return Ok(());
+    }
+
+    Ok(git.run_quietly()?)
+}
+
+/// Fuzzy picks a worktree of the current repository and attaches its tmux session
+pub fn pick(config: &Config) -> anyhow::Result<()> {
+    let repo = Repo::discover()?;
+    let worktrees = repo
+        .worktrees()?
+        .into_iter()
+        .map(|path| (repo.display_name(&path), path))
+        .collect();
+
+    let Some((name, path)) = select("worktrees", worktrees, |(name, _), _| name.as_str().into())?
+    else {
+        return Ok(());
+    };
+
+    Ok(tmux::find_or_create(name.as_str().into(), &path, config)?.attach_or_switch()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worktrees_of_regular_clones_are_siblings() {
+        assert_eq!(
+            PathBuf::from("/src/github.com/skipkayhil/sdev@feature-pickers"),
+            path_for(
+                Path::new("/src/github.com/skipkayhil/sdev"),
+                false,
+                "feature/pickers"
+            )
+        );
+    }
+
+    #[test]
+    fn worktrees_of_bare_layout_clones_are_inside() {
+        assert_eq!(
+            PathBuf::from("/src/github.com/skipkayhil/sdev/feature/pickers"),
+            path_for(
+                Path::new("/src/github.com/skipkayhil/sdev"),
+                true,
+                "feature/pickers"
+            )
+        );
+    }
+}
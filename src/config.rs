@@ -1,7 +1,93 @@
-use std::path::PathBuf;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use gix::url::Scheme;
+
+use crate::dep::git::{Layout, Retry};
+use crate::repo::rewrite::Rule;
+use crate::shell::tmux::Naming;
+use crate::shell::tmux::layout::Window;
+
+/// Which transport clones of names and paths use
+#[derive(Clone, Copy)]
+pub enum Protocol {
+    Ssh,
+    /// Authenticates through git credential helpers
+    Https,
+    /// SSH when an agent or key is available, HTTPS otherwise
+    Auto,
+}
+
+impl Protocol {
+    pub fn scheme(self) -> Scheme {
+        match self {
+            Protocol::Ssh => Scheme::Ssh,
+            Protocol::Https => Scheme::Https,
+            Protocol::Auto => {
+                let agent = env::var_os("SSH_AUTH_SOCK").is_some_and(|sock| !sock.is_empty());
+                let home = env::home_dir().expect("unknown HOME directory");
+
+                if agent || has_ssh_key(&home) {
+                    Scheme::Ssh
+                } else {
+                    Scheme::Https
+                }
+            }
+        }
+    }
+}
+
+fn has_ssh_key(home: &Path) -> bool {
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .any(|key| home.join(".ssh").join(key).is_file())
+}
 
 pub struct Config {
+    /// Retry policy for the fetch phase of clones
+    pub fetch_retry: Retry,
     pub host: String,
+    /// Directory names or paths relative to `root` that aren't searched for repositories
+    pub ignore: Vec<String>,
+    /// How new clones arrange their repository and working trees
+    pub layout: Layout,
+    /// How many directories below `root` repositories are searched for
+    pub max_depth: Option<usize>,
+    /// Rewrites applied to clone URLs before git's own `insteadOf` rules
+    pub mirrors: Vec<Rule>,
+    /// Owners besides `user` that short names are looked up under, in order
+    pub owners: Vec<String>,
+    /// Whether `sdev tmux` starts with the preview pane shown
+    pub preview: bool,
+    pub protocol: Protocol,
     pub root: PathBuf,
+    /// How repositories' tmux sessions are named, before telling apart ones that share a name
+    pub session_naming: Naming,
     pub user: String,
+    /// The windows of new tmux sessions, unless the repository's trusted recipe declares its own
+    pub windows: Vec<Window>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            fetch_retry: Retry::default(),
+            host: "github.com".to_string(),
+            ignore: ["node_modules", "target", "vendor"]
+                .map(String::from)
+                .to_vec(),
+            layout: Layout::default(),
+            max_depth: None,
+            mirrors: vec![],
+            owners: vec![],
+            preview: true,
+            protocol: Protocol::Auto,
+            root: std::env::home_dir()
+                .expect("unknown HOME directory")
+                .join("src"),
+            session_naming: Naming::default(),
+            user: "skipkayhil".to_string(),
+            windows: vec![],
+        }
+    }
 }
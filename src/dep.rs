@@ -1,14 +1,43 @@
+use std::fmt;
+
+pub mod command;
+pub mod executor;
+pub mod fs;
 pub mod git;
+pub mod plan;
+pub mod report;
+pub mod tmux;
 
-type Unmeetable = anyhow::Error;
-type MetResult = Result<Status, Unmeetable>;
-type MeetResult = Result<(), Unmeetable>;
-type Reqs = Vec<Box<dyn Dep>>;
+pub type Unmeetable = anyhow::Error;
+pub type MetResult = Result<Status, Unmeetable>;
+pub type MeetResult = Result<(), Unmeetable>;
+pub type Reqs = Vec<Box<dyn Dep>>;
 
 #[derive(thiserror::Error, Debug)]
 #[error("dep is unmet after meet")]
 struct UnmetAfterMeet;
 
+/// Wraps the error of a failed dep with its name and the deps that required it
+#[derive(thiserror::Error, Debug)]
+pub struct Failed {
+    name: String,
+    parents: Vec<String>,
+    #[source]
+    source: Unmeetable,
+}
+
+impl fmt::Display for Failed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed", self.name)?;
+
+        if !self.parents.is_empty() {
+            write!(f, " (required by {})", self.parents.join(" <- "))?;
+        }
+
+        Ok(())
+    }
+}
+
 pub enum Status {
     Met,
     Unmet,
@@ -54,7 +83,20 @@ mod status_tests {
     }
 }
 
-pub trait Dep {
+pub trait Dep: Send + Sync {
+    /// Identifies the dep in plans, reports and errors
+    fn name(&self) -> String;
+
+    /// Identifies the dep so identical requirements are only processed once
+    fn key(&self) -> String {
+        self.name()
+    }
+
+    /// Describes the action taken by `meet`
+    fn describe(&self) -> String {
+        self.name()
+    }
+
     fn met(&self) -> MetResult;
     fn meet(&self) -> MeetResult;
 
@@ -66,31 +108,17 @@ pub trait Dep {
         vec![]
     }
 
-    fn process(&self) -> MeetResult {
-        for req in self.reqs_to_met().iter() {
-            req.process()?
-        }
-
-        if self.met()?.is_met() {
-            return Ok(());
-        }
-
-        for req in self.reqs_to_meet().iter() {
-            req.process()?
-        }
-
-        self.meet()?;
-
-        match self.met()? {
-            Status::Unmet => Err(UnmetAfterMeet)?,
-            Status::Met => Ok(()),
-        }
+    /// Whether `reqs_to_meet` are met one at a time in the order they're listed, for requirements
+    /// that build on each other
+    fn reqs_in_order(&self) -> bool {
+        false
     }
 }
 
 #[cfg(test)]
 mod dep_tests {
     use super::*;
+    use crate::dep::executor::Executor;
     use anyhow::bail;
 
     const MET: MetResult = Ok(Status::Met);
@@ -98,6 +126,10 @@ mod dep_tests {
 
     struct BlankDep;
     impl Dep for BlankDep {
+        fn name(&self) -> String {
+            "BlankDep".into()
+        }
+
         fn met(&self) -> MetResult {
             MET
         }
@@ -109,11 +141,15 @@ mod dep_tests {
 
     #[test]
     fn processable() {
-        assert!(BlankDep.process().is_ok());
+        assert!(Executor::default().run(&BlankDep).is_ok());
     }
 
     struct RaisingMetDep;
     impl Dep for RaisingMetDep {
+        fn name(&self) -> String {
+            "RaisingMetDep".into()
+        }
+
         fn met(&self) -> MetResult {
             bail!("error during met")
         }
@@ -125,11 +161,15 @@ mod dep_tests {
 
     #[test]
     fn handles_errors_in_met() {
-        assert!(RaisingMetDep.process().is_err());
+        assert!(Executor::default().run(&RaisingMetDep).is_err());
     }
 
     struct NeverMetDep;
     impl Dep for NeverMetDep {
+        fn name(&self) -> String {
+            "NeverMetDep".into()
+        }
+
         fn met(&self) -> MetResult {
             UNMET
         }
@@ -141,6 +181,6 @@ mod dep_tests {
 
     #[test]
     fn errors_when_unmet_after_meet() {
-        assert!(NeverMetDep.process().is_err());
+        assert!(Executor::default().run(&NeverMetDep).is_err());
     }
 }
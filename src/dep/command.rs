@@ -0,0 +1,204 @@
+use std::env;
+use std::ffi::OsStr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use anyhow::bail;
+
+use crate::dep::{Dep, MeetResult, MetResult};
+use crate::shell;
+
+/// An executable available on PATH
+///
+/// sdev doesn't know how to install arbitrary programs, so meeting this dep only explains what's
+/// missing.
+pub struct OnPath {
+    program: String,
+}
+
+impl OnPath {
+    pub fn new(program: &str) -> Self {
+        OnPath {
+            program: program.into(),
+        }
+    }
+}
+
+impl Dep for OnPath {
+    fn name(&self) -> String {
+        format!("command::OnPath({})", self.program)
+    }
+
+    fn describe(&self) -> String {
+        format!("install {}", self.program)
+    }
+
+    fn met(&self) -> MetResult {
+        let path = env::var_os("PATH").unwrap_or_default();
+
+        Ok(find_in(&self.program, &path).is_some().into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        bail!("{} is not installed or not on PATH", self.program)
+    }
+}
+
+/// How a `Run` dep decides whether its command needs to run
+#[derive(Clone)]
+pub enum Check {
+    /// The check command exits successfully
+    Succeeds(String),
+    /// The path exists
+    Exists(PathBuf),
+}
+
+/// A shell command run in a directory until its check passes
+pub struct Run {
+    dir: PathBuf,
+    command: String,
+    check: Check,
+}
+
+impl Run {
+    pub fn new(dir: PathBuf, command: String, check: Check) -> Self {
+        Run {
+            dir,
+            command,
+            check,
+        }
+    }
+}
+
+impl Dep for Run {
+    fn name(&self) -> String {
+        format!("command::Run({})", self.command)
+    }
+
+    fn key(&self) -> String {
+        format!("command::Run({}, {})", self.dir.display(), self.command)
+    }
+
+    fn describe(&self) -> String {
+        format!("run `{}` in {}", self.command, self.dir.display())
+    }
+
+    fn met(&self) -> MetResult {
+        match &self.check {
+            Check::Succeeds(command) => Ok(shell::new!("sh", "-c", command)
+                .in_dir(&self.dir)
+                .output(false)?
+                .status
+                .success()
+                .into()),
+            Check::Exists(path) => Ok(self.dir.join(path).exists().into()),
+        }
+    }
+
+    fn meet(&self) -> MeetResult {
+        let mut shell = shell::new!("sh", "-c", &self.command).in_dir(&self.dir);
+
+        if !shell.status()?.success() {
+            bail!("`{}` exited unsuccessfully", self.command)
+        }
+
+        Ok(())
+    }
+}
+
+fn find_in(program: &str, path: &OsStr) -> Option<PathBuf> {
+    env::split_paths(path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| {
+            candidate
+                .metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::executor::Executor;
+    use std::fs;
+
+    #[test]
+    fn run_until_path_exists() {
+        let tmp = tempfile::tempdir().unwrap();
+        let run = Run::new(
+            tmp.path().into(),
+            "cp .env.example .env".into(),
+            Check::Exists(".env".into()),
+        );
+
+        fs::write(tmp.path().join(".env.example"), "A=1").unwrap();
+        assert!(run.met().unwrap().is_unmet());
+
+        Executor::default().run(&run).unwrap();
+
+        assert_eq!("A=1", fs::read_to_string(tmp.path().join(".env")).unwrap());
+    }
+
+    #[test]
+    fn run_until_check_succeeds() {
+        let tmp = tempfile::tempdir().unwrap();
+        let run = Run::new(
+            tmp.path().into(),
+            "touch done".into(),
+            Check::Succeeds("test -f done".into()),
+        );
+
+        assert!(run.met().unwrap().is_unmet());
+
+        Executor::default().run(&run).unwrap();
+
+        assert!(run.met().unwrap().is_met());
+    }
+
+    #[test]
+    fn run_errors_when_command_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let run = Run::new(
+            tmp.path().into(),
+            "exit 1".into(),
+            Check::Exists("never".into()),
+        );
+
+        assert!(Executor::default().run(&run).is_err());
+    }
+
+    #[test]
+    fn on_path_is_met_by_installed_programs() {
+        assert!(OnPath::new("sh").met().unwrap().is_met());
+    }
+
+    #[test]
+    fn on_path_explains_missing_programs() {
+        let missing = OnPath::new("sdev-missing-program");
+
+        assert!(missing.met().unwrap().is_unmet());
+
+        let error = Executor::default().run(&missing).unwrap_err();
+
+        assert!(format!("{error:#}").contains("sdev-missing-program is not installed"));
+    }
+
+    #[test]
+    fn finds_executables_in_path_order() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+
+        fs::write(first.path().join("tool"), "").unwrap();
+        fs::write(second.path().join("tool"), "").unwrap();
+        fs::set_permissions(
+            second.path().join("tool"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+
+        assert_eq!(Some(second.path().join("tool")), find_in("tool", &path));
+        assert_eq!(None, find_in("other", &path));
+    }
+}
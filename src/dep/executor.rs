@@ -0,0 +1,679 @@
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use crate::dep::report::{Outcome, Record, Reporter};
+use crate::dep::{Dep, Failed, MeetResult, MetResult, Status, UnmetAfterMeet};
+
+#[derive(thiserror::Error, Debug)]
+#[error("dependency cycle: {}", .0.join(" -> "))]
+struct Cycle(Vec<String>);
+
+enum Handle<'a> {
+    Borrowed(&'a dyn Dep),
+    Owned(Box<dyn Dep>),
+}
+
+impl Handle<'_> {
+    fn get(&self) -> &dyn Dep {
+        match self {
+            Handle::Borrowed(dep) => *dep,
+            Handle::Owned(dep) => dep.as_ref(),
+        }
+    }
+}
+
+struct Node<'a> {
+    dep: Handle<'a>,
+    reqs_to_met: Vec<usize>,
+    reqs_to_meet: Vec<usize>,
+    in_order: bool,
+}
+
+/// Every dep reachable from a root, deduplicated by `Dep::key`
+struct Graph<'a> {
+    nodes: Vec<Node<'a>>,
+    keys: HashMap<String, usize>,
+}
+
+impl<'a> Graph<'a> {
+    fn build(root: &'a dyn Dep) -> Result<(Self, usize), Cycle> {
+        let mut graph = Graph {
+            nodes: vec![],
+            keys: HashMap::new(),
+        };
+
+        let root = graph.insert(Handle::Borrowed(root), &mut vec![])?;
+
+        Ok((graph, root))
+    }
+
+    fn insert(&mut self, handle: Handle<'a>, path: &mut Vec<String>) -> Result<usize, Cycle> {
+        let key = handle.get().key();
+
+        if let Some(start) = path.iter().position(|k| *k == key) {
+            let mut cycle = path.split_off(start);
+            cycle.push(key);
+            return Err(Cycle(cycle));
+        }
+
+        if let Some(&index) = self.keys.get(&key) {
+            return Ok(index);
+        }
+
+        path.push(key);
+
+        let reqs_to_met = handle
+            .get()
+            .reqs_to_met()
+            .into_iter()
+            .map(|req| self.insert(Handle::Owned(req), path))
+            .collect::<Result<_, _>>()?;
+        let reqs_to_meet = handle
+            .get()
+            .reqs_to_meet()
+            .into_iter()
+            .map(|req| self.insert(Handle::Owned(req), path))
+            .collect::<Result<_, _>>()?;
+
+        let key = path.pop().expect("key was pushed above");
+        let index = self.nodes.len();
+        let in_order = handle.get().reqs_in_order();
+
+        self.nodes.push(Node {
+            dep: handle,
+            reqs_to_met,
+            reqs_to_meet,
+            in_order,
+        });
+        self.keys.insert(key, index);
+
+        Ok(index)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Job {
+    Check(usize),
+    Meet(usize),
+}
+
+impl Job {
+    fn index(&self) -> usize {
+        match self {
+            Job::Check(i) | Job::Meet(i) => *i,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Idle,
+    AwaitingMetReqs,
+    AwaitingMeetReqs,
+    Done,
+}
+
+/// Tracks which deps are waiting on which requirements and queues jobs once they're unblocked
+///
+/// A dep's `reqs_to_meet` are only requested after its `met` check comes back unmet, matching the
+/// semantics of processing a dep sequentially.
+struct Schedule<'g, 'a> {
+    graph: &'g Graph<'a>,
+    states: Vec<State>,
+    waiting: Vec<usize>,
+    /// Requirements of deps with `reqs_in_order` that aren't requested yet, last first
+    queued: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+    requested_by: Vec<Option<usize>>,
+    elapsed: Vec<Duration>,
+    ready: Vec<Job>,
+}
+
+impl<'g, 'a> Schedule<'g, 'a> {
+    fn new(graph: &'g Graph<'a>) -> Self {
+        let len = graph.nodes.len();
+
+        Self {
+            graph,
+            states: vec![State::Idle; len],
+            waiting: vec![0; len],
+            queued: vec![vec![]; len],
+            dependents: vec![vec![]; len],
+            requested_by: vec![None; len],
+            elapsed: vec![Duration::ZERO; len],
+            ready: vec![],
+        }
+    }
+
+    fn request(&mut self, i: usize, parent: Option<usize>) {
+        if self.states[i] != State::Idle {
+            return;
+        }
+
+        let graph = self.graph;
+
+        self.states[i] = State::AwaitingMetReqs;
+        self.requested_by[i] = parent;
+        self.wait_for(i, &graph.nodes[i].reqs_to_met);
+    }
+
+    fn checked(&mut self, i: usize, status: Status) {
+        if status.is_met() {
+            return self.done(i);
+        }
+
+        let graph = self.graph;
+
+        let node = &graph.nodes[i];
+
+        self.states[i] = State::AwaitingMeetReqs;
+
+        if node.in_order {
+            self.queued[i] = node.reqs_to_meet.iter().rev().copied().collect();
+            self.wait_for(i, &[]);
+        } else {
+            self.wait_for(i, &node.reqs_to_meet);
+        }
+    }
+
+    fn done(&mut self, i: usize) {
+        self.states[i] = State::Done;
+
+        for dependent in std::mem::take(&mut self.dependents[i]) {
+            self.waiting[dependent] -= 1;
+
+            if self.waiting[dependent] == 0 {
+                self.unblocked(dependent);
+            }
+        }
+    }
+
+    fn wait_for(&mut self, i: usize, reqs: &[usize]) {
+        for &req in reqs {
+            self.request(req, Some(i));
+
+            if self.states[req] != State::Done {
+                self.dependents[req].push(i);
+                self.waiting[i] += 1;
+            }
+        }
+
+        if self.waiting[i] == 0 {
+            self.unblocked(i);
+        }
+    }
+
+    /// Moves on once a dep isn't waiting on any requirement, to its next queued requirement or job
+    fn unblocked(&mut self, i: usize) {
+        if self.states[i] == State::AwaitingMeetReqs
+            && let Some(req) = self.queued[i].pop()
+        {
+            return self.wait_for(i, &[req]);
+        }
+
+        self.ready.push(match self.states[i] {
+            State::AwaitingMetReqs => Job::Check(i),
+            State::AwaitingMeetReqs => Job::Meet(i),
+            _ => unreachable!("only waiting deps have requirements outstanding"),
+        });
+    }
+
+    /// Names of the deps that led to `i` being requested, nearest first
+    fn parents(&self, mut i: usize) -> Vec<String> {
+        let mut names = vec![];
+
+        while let Some(parent) = self.requested_by[i] {
+            names.push(self.graph.nodes[parent].dep.get().name());
+            i = parent;
+        }
+
+        names
+    }
+}
+
+/// Processes a dep and its requirements, running independent deps on concurrent workers
+pub struct Executor<'r> {
+    workers: usize,
+    reporter: Option<&'r dyn Reporter>,
+}
+
+impl Default for Executor<'_> {
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+impl<'r> Executor<'r> {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            reporter: None,
+        }
+    }
+
+    pub fn with_reporter(self, reporter: &'r dyn Reporter) -> Self {
+        Self {
+            reporter: Some(reporter),
+            ..self
+        }
+    }
+
+    pub fn run(&self, dep: &dyn Dep) -> MeetResult {
+        let (graph, root) = Graph::build(dep)?;
+        let mut schedule = Schedule::new(&graph);
+
+        schedule.request(root, None);
+
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        let (result_tx, result_rx) = mpsc::channel::<(Job, MetResult, Duration)>();
+        let job_rx = Mutex::new(job_rx);
+
+        thread::scope(|scope| {
+            for _ in 0..self.workers.min(graph.nodes.len()) {
+                let job_rx = &job_rx;
+                let result_tx = result_tx.clone();
+                let graph = &graph;
+
+                scope.spawn(move || {
+                    loop {
+                        let job = job_rx.lock().unwrap().recv();
+                        let Ok(job) = job else { break };
+
+                        let start = Instant::now();
+                        let outcome = perform(graph.nodes[job.index()].dep.get(), job);
+
+                        if result_tx.send((job, outcome, start.elapsed())).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            drop(result_tx);
+
+            let mut in_flight = 0;
+            let mut error = None;
+
+            loop {
+                if error.is_none() {
+                    for job in schedule.ready.drain(..) {
+                        job_tx.send(job).expect("workers outlive the schedule");
+                        in_flight += 1;
+                    }
+                }
+
+                if in_flight == 0 {
+                    break;
+                }
+
+                let (job, outcome, elapsed) =
+                    result_rx.recv().expect("workers outlive the schedule");
+                let i = job.index();
+                let name = graph.nodes[i].dep.get().name();
+
+                in_flight -= 1;
+                schedule.elapsed[i] += elapsed;
+
+                let outcome = match (job, outcome) {
+                    (Job::Meet(_), Ok(Status::Unmet)) => Err(UnmetAfterMeet.into()),
+                    (_, outcome) => outcome,
+                };
+
+                match (job, outcome) {
+                    (_, Err(source)) => {
+                        self.report(&name, Outcome::Failed, schedule.elapsed[i]);
+                        error.get_or_insert(Failed {
+                            name,
+                            parents: schedule.parents(i),
+                            source,
+                        });
+                    }
+                    (Job::Check(_), Ok(status)) => {
+                        if status.is_met() {
+                            self.report(&name, Outcome::AlreadyMet, schedule.elapsed[i]);
+                        }
+
+                        schedule.checked(i, status);
+                    }
+                    (Job::Meet(_), Ok(_)) => {
+                        self.report(&name, Outcome::Met, schedule.elapsed[i]);
+                        schedule.done(i);
+                    }
+                }
+            }
+
+            drop(job_tx);
+
+            match error {
+                Some(e) => Err(e.into()),
+                None => {
+                    debug_assert!(schedule.states[root] == State::Done);
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    fn report(&self, name: &str, outcome: Outcome, elapsed: Duration) {
+        if let Some(reporter) = self.reporter {
+            reporter.record(Record {
+                name: name.into(),
+                outcome,
+                elapsed,
+            });
+        }
+    }
+}
+
+fn perform(dep: &dyn Dep, job: Job) -> MetResult {
+    panic::catch_unwind(AssertUnwindSafe(|| match job {
+        Job::Check(_) => dep.met(),
+        Job::Meet(_) => dep.meet().and_then(|()| dep.met()),
+    }))
+    .unwrap_or_else(|_| Err(anyhow!("{} panicked", dep.name())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::report::Report;
+    use anyhow::bail;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct Log {
+        met: Arc<Mutex<HashSet<&'static str>>>,
+        meets: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl Log {
+        fn meets(&self) -> Vec<&'static str> {
+            self.meets.lock().unwrap().clone()
+        }
+    }
+
+    #[derive(Clone)]
+    struct FakeDep {
+        key: &'static str,
+        log: Log,
+        reqs_to_met: Vec<FakeDep>,
+        reqs_to_meet: Vec<FakeDep>,
+        fails: bool,
+        in_order: bool,
+    }
+
+    impl FakeDep {
+        fn new(key: &'static str, log: &Log) -> Self {
+            Self {
+                key,
+                log: log.clone(),
+                reqs_to_met: vec![],
+                reqs_to_meet: vec![],
+                fails: false,
+                in_order: false,
+            }
+        }
+
+        fn met_by(mut self, reqs: &[&FakeDep]) -> Self {
+            self.reqs_to_met = reqs.iter().map(|&r| r.clone()).collect();
+            self
+        }
+
+        fn meet_by(mut self, reqs: &[&FakeDep]) -> Self {
+            self.reqs_to_meet = reqs.iter().map(|&r| r.clone()).collect();
+            self
+        }
+    }
+
+    impl Dep for FakeDep {
+        fn name(&self) -> String {
+            self.key.into()
+        }
+
+        fn met(&self) -> MetResult {
+            Ok(self.log.met.lock().unwrap().contains(self.key).into())
+        }
+
+        fn meet(&self) -> MeetResult {
+            if self.fails {
+                bail!("boom")
+            }
+
+            self.log.meets.lock().unwrap().push(self.key);
+            self.log.met.lock().unwrap().insert(self.key);
+            Ok(())
+        }
+
+        fn reqs_to_met(&self) -> Vec<Box<dyn Dep>> {
+            self.reqs_to_met
+                .iter()
+                .map(|r| Box::new(r.clone()) as Box<dyn Dep>)
+                .collect()
+        }
+
+        fn reqs_to_meet(&self) -> Vec<Box<dyn Dep>> {
+            self.reqs_to_meet
+                .iter()
+                .map(|r| Box::new(r.clone()) as Box<dyn Dep>)
+                .collect()
+        }
+
+        fn reqs_in_order(&self) -> bool {
+            self.in_order
+        }
+    }
+
+    #[test]
+    fn meets_requirements_before_dependents() {
+        let log = Log::default();
+        let a = FakeDep::new("a", &log);
+        let b = FakeDep::new("b", &log).meet_by(&[&a]);
+        let root = FakeDep::new("root", &log).met_by(&[&b]);
+
+        Executor::new(4).run(&root).unwrap();
+
+        assert_eq!(vec!["a", "b", "root"], log.meets());
+    }
+
+    #[test]
+    fn processes_shared_requirements_once() {
+        let log = Log::default();
+        let shared = FakeDep::new("shared", &log);
+        let a = FakeDep::new("a", &log).meet_by(&[&shared]);
+        let b = FakeDep::new("b", &log).meet_by(&[&shared]);
+        let root = FakeDep::new("root", &log).meet_by(&[&a, &b]);
+
+        Executor::new(4).run(&root).unwrap();
+
+        let meets = log.meets();
+        assert_eq!(4, meets.len());
+        assert_eq!("shared", meets[0]);
+        assert_eq!("root", meets[3]);
+    }
+
+    #[test]
+    fn meets_ordered_requirements_one_at_a_time() {
+        let log = Log::default();
+        let (a, b, c) = (
+            FakeDep::new("a", &log),
+            FakeDep::new("b", &log),
+            FakeDep::new("c", &log),
+        );
+        let mut root = FakeDep::new("root", &log).meet_by(&[&c, &a, &b]);
+        root.in_order = true;
+        log.met.lock().unwrap().insert("a");
+
+        Executor::new(4).run(&root).unwrap();
+
+        assert_eq!(vec!["c", "b", "root"], log.meets());
+    }
+
+    #[test]
+    fn skips_reqs_to_meet_when_already_met() {
+        let log = Log::default();
+        let req = FakeDep::new("req", &log);
+        let root = FakeDep::new("root", &log).meet_by(&[&req]);
+        log.met.lock().unwrap().insert("root");
+
+        Executor::new(4).run(&root).unwrap();
+
+        assert!(log.meets().is_empty());
+    }
+
+    #[test]
+    fn errors_on_cycles() {
+        let log = Log::default();
+        let inner = FakeDep::new("a", &log);
+        let b = FakeDep::new("b", &log).met_by(&[&inner]);
+        let root = FakeDep::new("a", &log).meet_by(&[&b]);
+
+        let error = Executor::new(4).run(&root).unwrap_err();
+
+        assert_eq!("dependency cycle: a -> b -> a", error.to_string());
+        assert!(log.meets().is_empty());
+    }
+
+    #[test]
+    fn stops_when_a_requirement_fails() {
+        let log = Log::default();
+        let mut failing = FakeDep::new("failing", &log);
+        failing.fails = true;
+        let parent = FakeDep::new("parent", &log).met_by(&[&failing]);
+        let root = FakeDep::new("root", &log).meet_by(&[&parent]);
+
+        let error = Executor::new(4).run(&root).unwrap_err();
+
+        assert_eq!(
+            "failing failed (required by parent <- root)",
+            error.to_string()
+        );
+        assert_eq!("boom", error.root_cause().to_string());
+        assert!(log.meets().is_empty());
+    }
+
+    #[test]
+    fn names_deps_that_are_unmet_after_meet() {
+        struct NeverMetDep;
+        impl Dep for NeverMetDep {
+            fn name(&self) -> String {
+                "never".into()
+            }
+
+            fn met(&self) -> MetResult {
+                Ok(Status::Unmet)
+            }
+
+            fn meet(&self) -> MeetResult {
+                Ok(())
+            }
+        }
+
+        let error = Executor::new(1).run(&NeverMetDep).unwrap_err();
+
+        assert_eq!("never failed", error.to_string());
+        assert_eq!("dep is unmet after meet", error.root_cause().to_string());
+    }
+
+    #[test]
+    fn reports_the_outcome_of_each_dep() {
+        let log = Log::default();
+        let met = FakeDep::new("met", &log);
+        log.met.lock().unwrap().insert("met");
+        let root = FakeDep::new("root", &log).meet_by(&[&met]);
+        let report = Report::default();
+
+        Executor::new(4).with_reporter(&report).run(&root).unwrap();
+
+        let records = report.records();
+        assert_eq!(2, records.len());
+        assert_eq!("met", records[0].name);
+        assert!(matches!(records[0].outcome, Outcome::AlreadyMet));
+        assert_eq!("root", records[1].name);
+        assert!(matches!(records[1].outcome, Outcome::Met));
+    }
+
+    #[derive(Clone)]
+    struct SlowDep {
+        key: String,
+        active: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl Dep for SlowDep {
+        fn name(&self) -> String {
+            self.key.clone()
+        }
+
+        fn met(&self) -> MetResult {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(Status::Met)
+        }
+
+        fn meet(&self) -> MeetResult {
+            Ok(())
+        }
+    }
+
+    struct FanOut(Vec<SlowDep>);
+
+    impl Dep for FanOut {
+        fn name(&self) -> String {
+            "fan-out".into()
+        }
+
+        fn met(&self) -> MetResult {
+            Ok(Status::Met)
+        }
+
+        fn meet(&self) -> MeetResult {
+            Ok(())
+        }
+
+        fn reqs_to_met(&self) -> Vec<Box<dyn Dep>> {
+            self.0
+                .iter()
+                .map(|r| Box::new(r.clone()) as Box<dyn Dep>)
+                .collect()
+        }
+    }
+
+    fn peak_concurrency(workers: usize) -> usize {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let fan_out = FanOut(
+            (0..4)
+                .map(|i| SlowDep {
+                    key: format!("slow-{i}"),
+                    active: active.clone(),
+                    peak: peak.clone(),
+                })
+                .collect(),
+        );
+
+        Executor::new(workers).run(&fan_out).unwrap();
+
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn runs_independent_deps_concurrently() {
+        assert!(peak_concurrency(4) > 1);
+    }
+
+    #[test]
+    fn limits_concurrency_to_worker_count() {
+        assert_eq!(1, peak_concurrency(1));
+    }
+}
@@ -0,0 +1,284 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix;
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+use crate::dep::{Dep, MeetResult, MetResult, Reqs, Status};
+
+fn parent_dir(path: &Path) -> Reqs {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => vec![Box::new(Dir::new(parent.into()))],
+        _ => vec![],
+    }
+}
+
+pub struct Dir {
+    path: PathBuf,
+}
+
+impl Dir {
+    pub fn new(path: PathBuf) -> Self {
+        Dir { path }
+    }
+}
+
+impl Dep for Dir {
+    fn name(&self) -> String {
+        format!("fs::Dir({})", self.path.display())
+    }
+
+    fn describe(&self) -> String {
+        format!("create directory {}", self.path.display())
+    }
+
+    fn met(&self) -> MetResult {
+        Ok(self.path.is_dir().into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        Ok(fs::create_dir_all(&self.path)?)
+    }
+}
+
+pub struct Symlink {
+    path: PathBuf,
+    target: PathBuf,
+}
+
+impl Symlink {
+    pub fn new(path: PathBuf, target: PathBuf) -> Self {
+        Symlink { path, target }
+    }
+}
+
+impl Dep for Symlink {
+    fn name(&self) -> String {
+        format!("fs::Symlink({})", self.path.display())
+    }
+
+    fn key(&self) -> String {
+        format!(
+            "fs::Symlink({}, {})",
+            self.path.display(),
+            self.target.display()
+        )
+    }
+
+    fn describe(&self) -> String {
+        format!("link {} to {}", self.path.display(), self.target.display())
+    }
+
+    fn met(&self) -> MetResult {
+        match fs::read_link(&self.path) {
+            Ok(target) => Ok((target == self.target).into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Status::Unmet),
+            Err(e) if e.kind() == ErrorKind::InvalidInput => {
+                bail!("{} exists and is not a symlink", self.path.display())
+            }
+            Err(e) => Err(e)?,
+        }
+    }
+
+    fn meet(&self) -> MeetResult {
+        // met has already ensured anything at path is a symlink, so it's safe to replace
+        if self.path.is_symlink() {
+            fs::remove_file(&self.path)?;
+        }
+
+        Ok(unix::fs::symlink(&self.target, &self.path)?)
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        parent_dir(&self.path)
+    }
+}
+
+/// A file with exactly the given contents
+pub struct File {
+    path: PathBuf,
+    contents: String,
+}
+
+impl File {
+    pub fn new(path: PathBuf, contents: String) -> Self {
+        File { path, contents }
+    }
+}
+
+impl Dep for File {
+    fn name(&self) -> String {
+        format!("fs::File({})", self.path.display())
+    }
+
+    fn key(&self) -> String {
+        format!("fs::File({}, {:?})", self.path.display(), self.contents)
+    }
+
+    fn describe(&self) -> String {
+        format!("write {}", self.path.display())
+    }
+
+    fn met(&self) -> MetResult {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok((contents == self.contents).into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Status::Unmet),
+            Err(e) => Err(e)?,
+        }
+    }
+
+    fn meet(&self) -> MeetResult {
+        Ok(fs::write(&self.path, &self.contents)?)
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        parent_dir(&self.path)
+    }
+}
+
+/// A file containing the given block of text, which is appended when missing
+pub struct Block {
+    path: PathBuf,
+    block: String,
+}
+
+impl Block {
+    pub fn new(path: PathBuf, block: String) -> Self {
+        Block { path, block }
+    }
+}
+
+impl Dep for Block {
+    fn name(&self) -> String {
+        format!("fs::Block({})", self.path.display())
+    }
+
+    fn key(&self) -> String {
+        format!("fs::Block({}, {:?})", self.path.display(), self.block)
+    }
+
+    fn describe(&self) -> String {
+        format!("append block to {}", self.path.display())
+    }
+
+    fn met(&self) -> MetResult {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents.contains(&self.block).into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Status::Unmet),
+            Err(e) => Err(e)?,
+        }
+    }
+
+    fn meet(&self) -> MeetResult {
+        let mut contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => Err(e)?,
+        };
+
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+
+        contents.push_str(&self.block);
+
+        if !self.block.ends_with('\n') {
+            contents.push('\n');
+        }
+
+        Ok(fs::write(&self.path, contents)?)
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        parent_dir(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::executor::Executor;
+
+    #[test]
+    fn dir_creates_missing_parents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = Dir::new(tmp.path().join("a/b"));
+
+        assert!(dir.met().unwrap().is_unmet());
+
+        Executor::default().run(&dir).unwrap();
+
+        assert!(tmp.path().join("a/b").is_dir());
+    }
+
+    #[test]
+    fn symlink_replaces_links_to_other_targets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("links/current");
+        let symlink = Symlink::new(path.clone(), tmp.path().join("new"));
+
+        fs::create_dir(tmp.path().join("links")).unwrap();
+        unix::fs::symlink(tmp.path().join("old"), &path).unwrap();
+
+        assert!(symlink.met().unwrap().is_unmet());
+
+        Executor::default().run(&symlink).unwrap();
+
+        assert_eq!(tmp.path().join("new"), fs::read_link(path).unwrap());
+    }
+
+    #[test]
+    fn symlink_refuses_to_replace_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("file");
+        fs::write(&path, "precious").unwrap();
+
+        let symlink = Symlink::new(path.clone(), tmp.path().join("target"));
+
+        assert!(Executor::default().run(&symlink).is_err());
+        assert_eq!("precious", fs::read_to_string(path).unwrap());
+    }
+
+    #[test]
+    fn file_overwrites_different_contents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config/.env");
+        let file = File::new(path.clone(), "A=1\n".into());
+
+        Executor::default().run(&file).unwrap();
+        assert_eq!("A=1\n", fs::read_to_string(&path).unwrap());
+
+        fs::write(&path, "A=2\n").unwrap();
+        assert!(file.met().unwrap().is_unmet());
+
+        Executor::default().run(&file).unwrap();
+        assert_eq!("A=1\n", fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn block_is_appended_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(".bashrc");
+        fs::write(&path, "export A=1").unwrap();
+
+        let block = Block::new(path.clone(), "export B=2".into());
+
+        Executor::default().run(&block).unwrap();
+        Executor::default().run(&block).unwrap();
+
+        assert_eq!(
+            "export A=1\nexport B=2\n",
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn conflicting_files_and_links_are_distinct_deps() {
+        let file = |contents: &str| File::new("/repo/.env".into(), contents.into());
+        let link = |target: &str| Symlink::new("/repo/.env".into(), target.into());
+
+        assert_ne!(file("A=1").key(), file("A=2").key());
+        assert_ne!(link("/a").key(), link("/b").key());
+    }
+}
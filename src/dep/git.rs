@@ -1,9 +1,17 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
+use anyhow::{anyhow, bail};
+use bstr::ByteSlice;
 use gix::Url;
 use gix::interrupt::IS_INTERRUPTED;
 use gix::progress::Discard;
+use gix::protocol::transport::IsSpuriousError;
+use gix::remote::Direction;
+use gix::worktree::stack::state::attributes::Source;
 use ratatui_core::{
     style::Stylize,
     terminal::{TerminalOptions, Viewport},
@@ -12,17 +20,148 @@ use ratatui_core::{
 };
 use ratatui_widgets::paragraph::Paragraph;
 
-use crate::dep::{Dep, MeetResult, MetResult};
+use crate::dep::command::OnPath;
+use crate::dep::{Dep, MeetResult, MetResult, Reqs, Status};
+use crate::shell;
 use crate::ui::ratinit::{self, DefaultTerminal};
 
+/// How many times to attempt a network operation, doubling the delay after each failure
+#[derive(Clone, Copy)]
+pub struct Retry {
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Retry {
+    /// Runs `op` until it succeeds or fails for good, calling `on_retry` before each new attempt
+    fn run<T>(
+        &self,
+        mut op: impl FnMut() -> anyhow::Result<T>,
+        mut on_retry: impl FnMut(&anyhow::Error, Duration) -> anyhow::Result<()>,
+    ) -> anyhow::Result<T> {
+        let mut attempt = 1;
+        let mut delay = self.delay;
+
+        loop {
+            match op() {
+                Ok(t) => return Ok(t),
+                Err(e) if attempt >= self.attempts || !is_transient(&e) => return Err(e),
+                Err(e) => {
+                    on_retry(&e, delay)?;
+                    thread::sleep(delay);
+
+                    attempt += 1;
+                    delay *= 2;
+                }
+            }
+        }
+    }
+}
+
+/// Whether an error could go away by trying again, like a dropped connection, unlike a missing
+/// repository or rejected credentials
+fn is_transient(error: &anyhow::Error) -> bool {
+    use gix::clone::fetch::Error;
+
+    match error.downcast_ref::<Error>() {
+        Some(Error::Connect(e)) => e.is_spurious(),
+        Some(Error::PrepareFetch(e)) => e.is_spurious(),
+        Some(Error::Fetch(e)) => e.is_spurious(),
+        Some(_) => false,
+        None => error
+            .downcast_ref::<io::Error>()
+            .is_some_and(IsSpuriousError::is_spurious),
+    }
+}
+
+/// Where the bare repository of a `Layout::Worktrees` clone lives, relative to its path
+pub const BARE_DIR: &str = ".bare";
+
+/// How a clone arranges its repository and working trees
+#[derive(Clone, Copy, Default)]
+pub enum Layout {
+    /// A regular repository checked out at the clone's path
+    #[default]
+    Standard,
+    /// A bare repository in `.bare`, found through a `.git` file, with a worktree directory per
+    /// branch next to it
+    Worktrees,
+}
+
 pub struct Clone {
     url: Url,
+    push_url: Option<Url>,
     path: PathBuf,
+    layout: Layout,
+    retry: Retry,
 }
 
 impl Clone {
     pub fn new(url: Url, path: PathBuf) -> Self {
-        Clone { url, path }
+        Clone {
+            url,
+            push_url: None,
+            path,
+            layout: Layout::default(),
+            retry: Retry::default(),
+        }
+    }
+
+    pub fn with_layout(self, layout: Layout) -> Self {
+        Self { layout, ..self }
+    }
+
+    pub fn with_retry(self, retry: Retry) -> Self {
+        Self { retry, ..self }
+    }
+
+    /// Pushes to a different URL than the one fetched from
+    pub fn with_push_url(self, push_url: Option<Url>) -> Self {
+        Self { push_url, ..self }
+    }
+
+    /// The layout of what's already at the path, which wins over the requested one so an
+    /// existing clone is resumed or kept as it is
+    fn layout(&self) -> Layout {
+        if self.path.join(".git").is_dir() {
+            Layout::Standard
+        } else if self.path.join(BARE_DIR).is_dir() {
+            Layout::Worktrees
+        } else {
+            self.layout
+        }
+    }
+
+    fn git_dir(&self) -> PathBuf {
+        match self.layout() {
+            Layout::Standard => self.path.join(".git"),
+            Layout::Worktrees => self.path.join(BARE_DIR),
+        }
+    }
+
+    /// A previous clone fetched successfully but failed to check out
+    fn is_fetched(&self) -> bool {
+        self.git_dir().is_dir()
+    }
+
+    /// Where the default branch is checked out once the clone is met
+    pub fn workdir(&self) -> PathBuf {
+        match self.layout() {
+            Layout::Standard => self.path.clone(),
+            Layout::Worktrees => gix::open(self.git_dir())
+                .ok()
+                .and_then(|repo| default_branch(&repo).ok().flatten())
+                .map_or_else(|| self.path.clone(), |branch| self.path.join(branch)),
+        }
     }
 
     fn run(&self, terminal: &mut DefaultTerminal) -> MeetResult {
@@ -37,27 +176,58 @@ impl Clone {
             ])
             .render(buf.area, buf);
         })?;
+        if let Some(push_url) = &self.push_url {
+            terminal.insert_before(1, |buf| {
+                Line::from(vec!["push".dark_gray(), format!(" {push_url}").into()])
+                    .render(buf.area, buf);
+            })?;
+        }
 
-        fs::create_dir_all(&self.path)?;
+        let repo = if self.is_fetched() {
+            terminal.insert_before(1, |buf| {
+                Line::from(vec![
+                    "✓".green(),
+                    " resuming from fetched repository".into(),
+                ])
+                .render(buf.area, buf);
+            })?;
 
-        let mut prepare_clone = gix::prepare_clone(self.url.clone(), &self.path)?;
+            gix::open(self.git_dir())?
+        } else {
+            terminal.draw(|f| {
+                f.render_widget("cloning...", f.area());
+            })?;
 
-        terminal.draw(|f| {
-            f.render_widget("cloning...", f.area());
-        })?;
+            let repo = self.retry.run(
+                || self.fetch(),
+                |e, delay| {
+                    terminal.insert_before(1, |buf| {
+                        Line::from(vec![
+                            "✗".red(),
+                            format!(" {e}, retrying in {}s", delay.as_secs_f32()).into(),
+                        ])
+                        .render(buf.area, buf);
+                    })?;
 
-        let (mut prepare_checkout, _) =
-            prepare_clone.fetch_then_checkout(Discard, &IS_INTERRUPTED)?;
+                    Ok(())
+                },
+            )?;
 
-        terminal.insert_before(1, |buf| {
-            Line::from(vec!["✓".green(), " fetched".into()]).render(buf.area, buf);
-        })?;
+            terminal.insert_before(1, |buf| {
+                Line::from(vec!["✓".green(), " fetched".into()]).render(buf.area, buf);
+            })?;
+
+            repo
+        };
 
         terminal.draw(|f| {
             f.render_widget("checking out...", f.area());
         })?;
 
-        prepare_checkout.main_worktree(Discard, &IS_INTERRUPTED)?;
+        match self.layout() {
+            Layout::Standard => checkout(&repo)?,
+            Layout::Worktrees => add_worktree(&repo, &self.path)?,
+        }
 
         terminal.insert_before(1, |buf| {
             Paragraph::new(Line::from(vec!["✓".green(), " cloned".into()])).render(buf.area, buf);
@@ -65,11 +235,165 @@ impl Clone {
 
         Ok(())
     }
+
+    /// Fetches into a new repository, removing it and any directories created for it again if
+    /// the fetch fails
+    fn fetch(&self) -> anyhow::Result<gix::Repository> {
+        let created: Vec<PathBuf> = self
+            .path
+            .ancestors()
+            .take_while(|dir| !dir.exists())
+            .map(Into::into)
+            .collect();
+
+        fs::create_dir_all(&self.path)?;
+
+        let prepare_clone = match self.layout {
+            Layout::Standard => gix::prepare_clone(self.url.clone(), &self.path),
+            Layout::Worktrees => gix::prepare_clone_bare(self.url.clone(), self.git_dir()),
+        };
+
+        let repo = prepare_clone
+            .map_err(anyhow::Error::from)
+            .and_then(|prepare_clone| self.fetch_with(prepare_clone));
+
+        if repo.is_err() {
+            // gix only removes the repository it created, deepest directories come first
+            for dir in &created {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+
+        let repo = repo?;
+
+        if let Layout::Worktrees = self.layout {
+            fs::write(self.path.join(".git"), format!("gitdir: ./{BARE_DIR}\n"))?;
+        }
+
+        Ok(repo)
+    }
+
+    fn fetch_with(
+        &self,
+        prepare_clone: gix::clone::PrepareFetch,
+    ) -> anyhow::Result<gix::Repository> {
+        // HTTPS remotes authenticate through git's credential helpers, prompting on the terminal
+        // when none of them has an answer
+        let mut prepare_clone = prepare_clone.configure_connection(|connection| {
+            if let Some(url) = connection.remote().url(Direction::Fetch).cloned() {
+                let credentials = connection.configured_credentials(url)?;
+                connection.set_credentials(credentials);
+            }
+
+            Ok(())
+        });
+        let (repo, _) = prepare_clone.fetch_only(Discard, &IS_INTERRUPTED)?;
+
+        if let Some(push_url) = &self.push_url {
+            set_push_url(&repo, push_url)?;
+        }
+
+        Ok(repo)
+    }
+}
+
+/// Writes `remote.<name>.pushurl` for the default remote to the repository's local config
+fn set_push_url(repo: &gix::Repository, push_url: &Url) -> anyhow::Result<()> {
+    let remote = repo
+        .remote_default_name(Direction::Fetch)
+        .ok_or_else(|| anyhow!("{} has no remote", repo.git_dir().display()))?;
+    let path = repo.git_dir().join("config");
+
+    let mut config =
+        gix::config::File::from_path_no_includes(path.clone(), gix::config::Source::Local)?;
+    config.set_raw_value_by(
+        "remote",
+        Some(remote.as_ref()),
+        "pushurl",
+        push_url.to_bstring().as_bstr(),
+    )?;
+
+    Ok(fs::write(path, config.to_bstring())?)
+}
+
+/// The short name of the branch HEAD points to, if it has any commits
+fn default_branch(repo: &gix::Repository) -> anyhow::Result<Option<String>> {
+    if repo.head()?.is_unborn() {
+        return Ok(None);
+    }
+
+    Ok(repo.head_name()?.map(|name| name.shorten().to_string()))
+}
+
+/// Adds a worktree for the default branch of a freshly fetched bare repository
+fn add_worktree(repo: &gix::Repository, path: &Path) -> anyhow::Result<()> {
+    // an empty remote has nothing to check out
+    let Some(branch) = default_branch(repo)? else {
+        return Ok(());
+    };
+
+    Ok(shell::new!("git", "worktree", "add", &branch, &branch)
+        .in_dir(path)
+        .run_quietly()?)
+}
+
+/// Checks out HEAD into the worktree of a freshly fetched repository
+///
+/// Files left behind by an earlier, interrupted checkout are overwritten.
+fn checkout(repo: &gix::Repository) -> anyhow::Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("{} is bare", repo.git_dir().display()))?;
+
+    // an empty remote has nothing to check out
+    let Some(head) = repo.head()?.try_peel_to_id()? else {
+        return Ok(());
+    };
+
+    let tree = head.object()?.peel_to_tree()?.id;
+    let mut index = repo.index_from_tree(&tree)?;
+    let mut options = repo.checkout_options(Source::IdMapping)?;
+    options.overwrite_existing = true;
+
+    gix::worktree::state::checkout(
+        &mut index,
+        workdir,
+        repo.objects.clone().into_arc()?,
+        &Discard,
+        &Discard,
+        &IS_INTERRUPTED,
+        options,
+    )?;
+
+    index.write(Default::default())?;
+
+    Ok(())
 }
 
 impl Dep for Clone {
+    fn name(&self) -> String {
+        format!("git::Clone({})", self.path.display())
+    }
+
+    fn describe(&self) -> String {
+        format!("clone {} into {}", self.url, self.path.display())
+    }
+
     fn met(&self) -> MetResult {
-        Ok(self.path.join(".git").is_dir().into())
+        if !self.is_fetched() {
+            return Ok(Status::Unmet);
+        }
+
+        let repo = gix::open(self.git_dir())?;
+
+        match self.layout() {
+            Layout::Standard => {
+                Ok((repo.index_path().is_file() || repo.head()?.is_unborn()).into())
+            }
+            Layout::Worktrees => Ok((self.path.join(".git").is_file()
+                && (!repo.worktrees()?.is_empty() || repo.head()?.is_unborn()))
+            .into()),
+        }
     }
 
     fn meet(&self) -> MeetResult {
@@ -84,3 +408,545 @@ impl Dep for Clone {
         result
     }
 }
+
+/// A remote in an existing repository with the given fetch URL
+pub struct Remote {
+    repo: PathBuf,
+    name: String,
+    url: Url,
+}
+
+impl Remote {
+    pub fn new(repo: PathBuf, name: String, url: Url) -> Self {
+        Remote { repo, name, url }
+    }
+}
+
+impl Dep for Remote {
+    fn name(&self) -> String {
+        format!("git::Remote({}, {})", self.repo.display(), self.name)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "set remote {} of {} to {}",
+            self.name,
+            self.repo.display(),
+            self.url
+        )
+    }
+
+    fn met(&self) -> MetResult {
+        let repo = gix::open(&self.repo)?;
+
+        let Some(remote) = repo.try_find_remote_without_url_rewrite(self.name.as_str()) else {
+            return Ok(Status::Unmet);
+        };
+
+        Ok(remote?
+            .url(Direction::Fetch)
+            .is_some_and(|url| url.to_bstring() == self.url.to_bstring())
+            .into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        let repo = gix::open(&self.repo)?;
+        let subcommand = if repo
+            .try_find_remote_without_url_rewrite(self.name.as_str())
+            .is_some()
+        {
+            "set-url"
+        } else {
+            "add"
+        };
+
+        Ok(shell::new!(
+            "git",
+            "-C",
+            &self.repo,
+            "remote",
+            subcommand,
+            &self.name,
+            self.url.to_bstring().to_string()
+        )
+        .run_quietly()?)
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        vec![Box::new(OnPath::new("git"))]
+    }
+}
+
+/// A linked worktree of an existing repository with a branch checked out
+///
+/// The branch is created from the remote's branch of the same name when there is one, or from
+/// HEAD otherwise.
+pub struct Worktree {
+    repo: PathBuf,
+    path: PathBuf,
+    branch: String,
+}
+
+impl Worktree {
+    pub fn new(repo: PathBuf, path: PathBuf, branch: String) -> Self {
+        Worktree { repo, path, branch }
+    }
+}
+
+impl Dep for Worktree {
+    fn name(&self) -> String {
+        format!("git::Worktree({})", self.path.display())
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "add worktree for {} at {}",
+            self.branch,
+            self.path.display()
+        )
+    }
+
+    fn met(&self) -> MetResult {
+        let repo = gix::open(&self.repo)?;
+
+        Ok(has_worktree(&repo, &self.path, &self.branch)?.into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        let repo = gix::open(&self.repo)?;
+        let mut git = shell::new!("git", "worktree", "add").in_dir(&self.repo);
+
+        let exists = [
+            format!("refs/heads/{}", self.branch),
+            format!("refs/remotes/origin/{}", self.branch),
+        ]
+        .iter()
+        .any(|name| matches!(repo.try_find_reference(name.as_str()), Ok(Some(_))));
+
+        // without -b, git checks out an existing branch or tracks the remote one
+        if !exists {
+            git.arg("-b").arg(&self.branch);
+        }
+
+        git.arg(&self.path);
+
+        if exists {
+            git.arg(&self.branch);
+        }
+
+        Ok(git.run_quietly()?)
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        vec![Box::new(OnPath::new("git"))]
+    }
+}
+
+/// The directories of every linked worktree of a repository
+pub fn linked_worktrees(repo: &gix::Repository) -> anyhow::Result<Vec<PathBuf>> {
+    repo.worktrees()?
+        .iter()
+        .map(|worktree| Ok(worktree.base()?))
+        .collect()
+}
+
+/// Whether the linked worktree at `path` has `branch` checked out
+///
+/// Fails when a worktree of another branch is there, like one whose name sanitizes to the same
+/// directory.
+pub fn has_worktree(repo: &gix::Repository, path: &Path, branch: &str) -> anyhow::Result<bool> {
+    // git keeps the paths worktrees were added with, which may go through symlinks
+    let Ok(path) = path.canonicalize() else {
+        return Ok(false);
+    };
+
+    for worktree in repo.worktrees()? {
+        if worktree.base()?.canonicalize().ok().as_ref() != Some(&path) {
+            continue;
+        }
+
+        let worktree = worktree.into_repo_with_possibly_inaccessible_worktree()?;
+
+        return match worktree.head_name()? {
+            Some(name) if name.shorten() == branch => Ok(true),
+            Some(name) => bail!("{} is the worktree of {}", path.display(), name.shorten()),
+            None => bail!("{} is a worktree with a detached HEAD", path.display()),
+        };
+    }
+
+    Ok(false)
+}
+
+/// A key in an existing repository's config with the given value
+pub struct ConfigValue {
+    repo: PathBuf,
+    key: String,
+    value: String,
+}
+
+impl ConfigValue {
+    pub fn new(repo: PathBuf, key: String, value: String) -> Self {
+        ConfigValue { repo, key, value }
+    }
+}
+
+impl Dep for ConfigValue {
+    fn name(&self) -> String {
+        format!("git::ConfigValue({}, {})", self.repo.display(), self.key)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "set {} to \"{}\" in {}",
+            self.key,
+            self.value,
+            self.repo.display()
+        )
+    }
+
+    fn met(&self) -> MetResult {
+        let repo = gix::open(&self.repo)?;
+
+        Ok(repo
+            .config_snapshot()
+            .string(self.key.as_str())
+            .is_some_and(|value| *value == self.value)
+            .into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        Ok(shell::new!("git", "-C", &self.repo, "config", &self.key, &self.value).run_quietly()?)
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        vec![Box::new(OnPath::new("git"))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::executor::Executor;
+    use anyhow::bail;
+    use std::path::Path;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=sdev", "-c", "user.email=sdev@example.com"])
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    /// A bare repository with one commit, standing in for a remote
+    fn bare_remote(tmp: &Path) -> Url {
+        let work = tmp.join("work");
+        fs::create_dir(&work).unwrap();
+        fs::write(work.join("README"), "hello").unwrap();
+
+        git(&work, &["init", "-q"]);
+        git(&work, &["add", "README"]);
+        git(&work, &["commit", "-q", "-m", "init"]);
+        git(tmp, &["clone", "-q", "--bare", "work", "remote.git"]);
+
+        Url::try_from(format!("file://{}", tmp.join("remote.git").display()).as_str()).unwrap()
+    }
+
+    #[test]
+    fn clone_resumes_checkout_from_fetched_repository() {
+        let tmp = tempfile::tempdir().unwrap();
+        let clone = Clone::new(bare_remote(tmp.path()), tmp.path().join("clone"));
+
+        clone.fetch().unwrap();
+
+        assert!(clone.is_fetched());
+        assert!(clone.met().unwrap().is_unmet());
+
+        checkout(&gix::open(tmp.path().join("clone")).unwrap()).unwrap();
+
+        assert!(clone.met().unwrap().is_met());
+        assert_eq!(
+            "hello",
+            fs::read_to_string(tmp.path().join("clone/README")).unwrap()
+        );
+    }
+
+    #[test]
+    fn clone_sets_push_url_on_default_remote() {
+        let tmp = tempfile::tempdir().unwrap();
+        let push_url = Url::try_from("git@github.com:rails/rails.git").unwrap();
+        let clone = Clone::new(bare_remote(tmp.path()), tmp.path().join("clone"))
+            .with_push_url(Some(push_url.clone()));
+
+        let repo = clone.fetch().unwrap();
+        let repo = gix::open(repo.git_dir()).unwrap();
+        let remote = repo.find_remote("origin").unwrap();
+
+        assert_eq!(Some(&push_url), remote.url(Direction::Push));
+    }
+
+    #[test]
+    fn clone_with_worktrees_layout_adds_default_branch_worktree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("clone");
+        let clone =
+            Clone::new(bare_remote(tmp.path()), path.clone()).with_layout(Layout::Worktrees);
+
+        let repo = clone.fetch().unwrap();
+
+        assert!(path.join(".bare").is_dir());
+        assert!(clone.met().unwrap().is_unmet());
+
+        add_worktree(&repo, &path).unwrap();
+
+        let branch = default_branch(&repo).unwrap().unwrap();
+        assert!(clone.met().unwrap().is_met());
+        assert_eq!(path.join(&branch), clone.workdir());
+        assert_eq!(
+            "hello",
+            fs::read_to_string(path.join(branch).join("README")).unwrap()
+        );
+        assert_eq!(
+            path.join(".bare").canonicalize().unwrap(),
+            gix::open(&path).unwrap().git_dir().canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn existing_clones_are_met_in_either_layout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let url = bare_remote(tmp.path());
+
+        let standard = tmp.path().join("standard");
+        let clone = Clone::new(url.clone(), standard.clone());
+        checkout(&clone.fetch().unwrap()).unwrap();
+
+        let worktrees = tmp.path().join("worktrees");
+        let clone = Clone::new(url.clone(), worktrees.clone()).with_layout(Layout::Worktrees);
+        add_worktree(&clone.fetch().unwrap(), &worktrees).unwrap();
+
+        let clone = Clone::new(url.clone(), standard.clone()).with_layout(Layout::Worktrees);
+        assert!(clone.met().unwrap().is_met());
+        assert_eq!(standard, clone.workdir());
+
+        let clone = Clone::new(url, worktrees.clone());
+        assert!(clone.met().unwrap().is_met());
+        assert_ne!(worktrees, clone.workdir());
+    }
+
+    #[test]
+    fn failed_worktrees_fetch_leaves_nothing_behind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let url =
+            Url::try_from(format!("file://{}/missing.git", tmp.path().display()).as_str()).unwrap();
+        let clone = Clone::new(url, tmp.path().join("clone")).with_layout(Layout::Worktrees);
+
+        assert!(clone.fetch().is_err());
+        assert!(!tmp.path().join("clone").exists());
+    }
+
+    #[test]
+    fn worktree_creates_missing_branch() {
+        let tmp = tempfile::tempdir().unwrap();
+        bare_remote(tmp.path());
+        let repo = tmp.path().join("work");
+        let worktree = Worktree::new(
+            repo.clone(),
+            tmp.path().join("work@feature"),
+            "feature".into(),
+        );
+
+        assert!(worktree.met().unwrap().is_unmet());
+
+        Executor::default().run(&worktree).unwrap();
+
+        assert!(worktree.met().unwrap().is_met());
+        assert_eq!(
+            "hello",
+            fs::read_to_string(tmp.path().join("work@feature/README")).unwrap()
+        );
+    }
+
+    #[test]
+    fn worktree_is_found_through_symlinks() {
+        let tmp = tempfile::tempdir().unwrap();
+        bare_remote(tmp.path());
+        std::os::unix::fs::symlink(tmp.path(), tmp.path().join("link")).unwrap();
+
+        let worktree = Worktree::new(
+            tmp.path().join("link/work"),
+            tmp.path().join("link/work@feature"),
+            "feature".into(),
+        );
+        Executor::default().run(&worktree).unwrap();
+
+        let repo = gix::open(tmp.path().join("work")).unwrap();
+
+        assert!(has_worktree(&repo, &tmp.path().join("work@feature"), "feature").unwrap());
+        assert!(worktree.met().unwrap().is_met());
+    }
+
+    #[test]
+    fn worktree_of_another_branch_is_an_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        bare_remote(tmp.path());
+        let repo = tmp.path().join("work");
+        let path = tmp.path().join("work@feature-a");
+
+        Executor::default()
+            .run(&Worktree::new(
+                repo.clone(),
+                path.clone(),
+                "feature-a".into(),
+            ))
+            .unwrap();
+
+        let error = Worktree::new(repo, path, "feature/a".into())
+            .met()
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().ends_with("is the worktree of feature-a"));
+    }
+
+    #[test]
+    fn failed_fetch_leaves_nothing_behind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let url =
+            Url::try_from(format!("file://{}/missing.git", tmp.path().display()).as_str()).unwrap();
+        let clone = Clone::new(url, tmp.path().join("clone"));
+
+        assert!(clone.fetch().is_err());
+        assert!(!tmp.path().join("clone").exists());
+    }
+
+    #[test]
+    fn failed_fetch_removes_created_parents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let url =
+            Url::try_from(format!("file://{}/missing.git", tmp.path().display()).as_str()).unwrap();
+        let clone = Clone::new(url, tmp.path().join("github.com/rails/rails"));
+
+        assert!(clone.fetch().is_err());
+        assert!(!tmp.path().join("github.com").exists());
+    }
+
+    #[test]
+    fn retry_backs_off_until_success() {
+        let retry = Retry {
+            attempts: 3,
+            delay: Duration::from_millis(1),
+        };
+        let mut attempts = 0;
+        let mut delays = vec![];
+
+        let result = retry.run(
+            || {
+                attempts += 1;
+
+                if attempts < 3 {
+                    Err(io::Error::from(io::ErrorKind::ConnectionReset))?
+                }
+
+                Ok(attempts)
+            },
+            |_, delay| {
+                delays.push(delay);
+                Ok(())
+            },
+        );
+
+        assert_eq!(3, result.unwrap());
+        assert_eq!(
+            vec![Duration::from_millis(1), Duration::from_millis(2)],
+            delays
+        );
+    }
+
+    #[test]
+    fn retry_gives_up_after_attempts() {
+        let retry = Retry {
+            attempts: 2,
+            delay: Duration::ZERO,
+        };
+        let mut attempts = 0;
+
+        let result: anyhow::Result<()> = retry.run(
+            || {
+                attempts += 1;
+                Err(io::Error::from(io::ErrorKind::TimedOut))?
+            },
+            |_, _| Ok(()),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(2, attempts);
+    }
+
+    #[test]
+    fn retry_gives_up_on_permanent_errors() {
+        let mut attempts = 0;
+
+        let result: anyhow::Result<()> = Retry::default().run(
+            || {
+                attempts += 1;
+                bail!("repository not found")
+            },
+            |_, _| Ok(()),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(1, attempts);
+    }
+
+    #[test]
+    fn remote_is_added_then_updated() {
+        let tmp = tempfile::tempdir().unwrap();
+        gix::init(tmp.path()).unwrap();
+
+        let first = Url::try_from("https://github.com/rails/rails.git").unwrap();
+        let remote = Remote::new(tmp.path().into(), "upstream".into(), first);
+
+        assert!(remote.met().unwrap().is_unmet());
+        Executor::default().run(&remote).unwrap();
+
+        let second = Url::try_from("git@github.com:rails/rails.git").unwrap();
+        let remote = Remote::new(tmp.path().into(), "upstream".into(), second.clone());
+
+        assert!(remote.met().unwrap().is_unmet());
+        Executor::default().run(&remote).unwrap();
+
+        let repo = gix::open(tmp.path()).unwrap();
+        let upstream = repo.find_remote("upstream").unwrap();
+
+        assert_eq!(Some(&second), upstream.url(Direction::Fetch));
+    }
+
+    #[test]
+    fn config_value_is_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        gix::init(tmp.path()).unwrap();
+
+        let value = ConfigValue::new(tmp.path().into(), "sdev.test".into(), "hello".into());
+
+        assert!(value.met().unwrap().is_unmet());
+        Executor::default().run(&value).unwrap();
+    }
+
+    #[test]
+    fn failed_config_is_reported_with_gits_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        gix::init(tmp.path()).unwrap();
+
+        let value = ConfigValue::new(tmp.path().into(), "nosection".into(), "hello".into());
+        let error = value.meet().unwrap_err().to_string();
+
+        assert!(error.contains("key does not contain a section"), "{error}");
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::dep::Dep;
+
+enum Outcome {
+    Met,
+    Unmet,
+    Errored(String),
+    Cycle,
+    Repeated,
+}
+
+struct Step {
+    description: String,
+    outcome: Outcome,
+    reqs: Vec<Step>,
+}
+
+/// What processing a dep would do, found by checking `met` across its requirements without ever
+/// calling `meet`
+///
+/// Requirements are checked in their current state, so a dep that depends on an unmet
+/// requirement may report unmet (or error) even though processing it would succeed.
+pub struct Plan(Step);
+
+impl Plan {
+    pub fn new(dep: &dyn Dep) -> Self {
+        Self(step(dep, &mut vec![], &mut HashSet::new()))
+    }
+}
+
+fn step(dep: &dyn Dep, path: &mut Vec<String>, seen: &mut HashSet<String>) -> Step {
+    let key = dep.key();
+    let description = dep.describe();
+
+    if path.contains(&key) {
+        return Step {
+            description,
+            outcome: Outcome::Cycle,
+            reqs: vec![],
+        };
+    }
+
+    if !seen.insert(key.clone()) {
+        return Step {
+            description,
+            outcome: Outcome::Repeated,
+            reqs: vec![],
+        };
+    }
+
+    path.push(key);
+
+    let mut reqs: Vec<Step> = dep
+        .reqs_to_met()
+        .iter()
+        .map(|req| step(req.as_ref(), path, seen))
+        .collect();
+
+    let outcome = match dep.met() {
+        Ok(status) if status.is_met() => Outcome::Met,
+        Ok(_) => {
+            reqs.extend(
+                dep.reqs_to_meet()
+                    .iter()
+                    .map(|req| step(req.as_ref(), path, seen)),
+            );
+
+            Outcome::Unmet
+        }
+        Err(e) => Outcome::Errored(e.to_string()),
+    };
+
+    path.pop();
+
+    Step {
+        description,
+        outcome,
+        reqs,
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_step(f, &self.0, 0)
+    }
+}
+
+fn write_step(f: &mut fmt::Formatter<'_>, step: &Step, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    let description = &step.description;
+
+    match &step.outcome {
+        Outcome::Met => writeln!(f, "{indent}✓ {description}")?,
+        Outcome::Unmet => writeln!(f, "{indent}✗ {description}")?,
+        Outcome::Errored(e) => writeln!(f, "{indent}! {description} ({e})")?,
+        Outcome::Cycle => writeln!(f, "{indent}! {description} (cycle)")?,
+        Outcome::Repeated => writeln!(f, "{indent}· {description} (see above)")?,
+    }
+
+    for req in &step.reqs {
+        write_step(f, req, depth + 1)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::{MeetResult, MetResult, Status};
+    use anyhow::bail;
+
+    #[derive(Clone)]
+    struct FakeDep {
+        name: &'static str,
+        met: bool,
+        reqs_to_met: Vec<FakeDep>,
+        reqs_to_meet: Vec<FakeDep>,
+    }
+
+    impl FakeDep {
+        fn new(name: &'static str, met: bool) -> Self {
+            Self {
+                name,
+                met,
+                reqs_to_met: vec![],
+                reqs_to_meet: vec![],
+            }
+        }
+    }
+
+    impl Dep for FakeDep {
+        fn name(&self) -> String {
+            self.name.into()
+        }
+
+        fn describe(&self) -> String {
+            format!("create {}", self.name)
+        }
+
+        fn met(&self) -> MetResult {
+            if self.name == "broken" {
+                bail!("no permission")
+            }
+
+            Ok(Status::from(self.met))
+        }
+
+        fn meet(&self) -> MeetResult {
+            panic!("plans never meet deps")
+        }
+
+        fn reqs_to_met(&self) -> Vec<Box<dyn Dep>> {
+            self.reqs_to_met
+                .iter()
+                .map(|r| Box::new(r.clone()) as Box<dyn Dep>)
+                .collect()
+        }
+
+        fn reqs_to_meet(&self) -> Vec<Box<dyn Dep>> {
+            self.reqs_to_meet
+                .iter()
+                .map(|r| Box::new(r.clone()) as Box<dyn Dep>)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn lists_reqs_to_meet_of_unmet_deps() {
+        let mut root = FakeDep::new("root", false);
+        root.reqs_to_met = vec![FakeDep::new("a", true)];
+        root.reqs_to_meet = vec![FakeDep::new("b", false), FakeDep::new("broken", false)];
+
+        assert_eq!(
+            "✗ create root\n  ✓ create a\n  ✗ create b\n  ! create broken (no permission)\n",
+            Plan::new(&root).to_string()
+        );
+    }
+
+    #[test]
+    fn omits_reqs_to_meet_of_met_deps() {
+        let mut root = FakeDep::new("root", true);
+        root.reqs_to_meet = vec![FakeDep::new("a", false)];
+
+        assert_eq!("✓ create root\n", Plan::new(&root).to_string());
+    }
+
+    #[test]
+    fn marks_repeated_and_cyclic_deps() {
+        let mut b = FakeDep::new("b", false);
+        b.reqs_to_met = vec![FakeDep::new("root", false)];
+        let mut root = FakeDep::new("root", false);
+        root.reqs_to_meet = vec![b.clone(), b];
+
+        assert_eq!(
+            "✗ create root\n  ✗ create b\n    ! create root (cycle)\n  · create b (see above)\n",
+            Plan::new(&root).to_string()
+        );
+    }
+}
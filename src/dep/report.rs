@@ -0,0 +1,60 @@
+use std::cell::{Ref, RefCell};
+use std::fmt;
+use std::time::Duration;
+
+pub enum Outcome {
+    /// `met` was satisfied without calling `meet`
+    AlreadyMet,
+    /// `meet` was called and the dep is now met
+    Met,
+    Failed,
+}
+
+pub struct Record {
+    pub name: String,
+    pub outcome: Outcome,
+    /// Time spent in the dep's own `met` and `meet`, excluding its requirements
+    pub elapsed: Duration,
+}
+
+/// Receives a record for every dep an `Executor` finishes processing
+pub trait Reporter {
+    fn record(&self, record: Record);
+}
+
+/// Collects records in the order deps finished
+#[derive(Default)]
+pub struct Report(RefCell<Vec<Record>>);
+
+impl Report {
+    pub fn records(&self) -> Ref<'_, Vec<Record>> {
+        self.0.borrow()
+    }
+}
+
+impl Reporter for Report {
+    fn record(&self, record: Record) {
+        self.0.borrow_mut().push(record);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for record in self.records().iter() {
+            let marker = match record.outcome {
+                Outcome::AlreadyMet => "·",
+                Outcome::Met => "✓",
+                Outcome::Failed => "✗",
+            };
+
+            writeln!(
+                f,
+                "{marker} {} ({:.2}s)",
+                record.name,
+                record.elapsed.as_secs_f64()
+            )?;
+        }
+
+        Ok(())
+    }
+}
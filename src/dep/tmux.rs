@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use crate::dep::command::OnPath;
+use crate::dep::{Dep, MeetResult, MetResult, Reqs};
+use crate::shell::tmux::{self, layout::Window};
+
+/// A detached tmux session started in the given directory, laid out with `windows`
+///
+/// A session by that name started in another directory belongs to another repository, so the dep
+/// fails instead of taking it over.
+pub struct Session {
+    name: String,
+    path: PathBuf,
+    windows: Vec<Window>,
+}
+
+impl Session {
+    pub fn new(name: String, path: PathBuf, windows: Vec<Window>) -> Self {
+        Session {
+            name,
+            path,
+            windows,
+        }
+    }
+}
+
+impl Dep for Session {
+    fn name(&self) -> String {
+        format!("tmux::Session({})", self.name)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "start tmux session {} in {}",
+            self.name,
+            self.path.display()
+        )
+    }
+
+    fn met(&self) -> MetResult {
+        Ok(tmux::Session::find_in(self.name.as_str(), &self.path)?
+            .is_some()
+            .into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        tmux::Session::find_or_create_in(self.name.as_str(), &self.path, &self.windows)?;
+
+        Ok(())
+    }
+
+    fn reqs_to_met(&self) -> Reqs {
+        vec![Box::new(OnPath::new("tmux"))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::executor::Executor;
+    use crate::shell::tmux::TestServer;
+
+    #[test]
+    fn session_is_started_once() {
+        let Some(_server) = TestServer::start() else {
+            return;
+        };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let name = format!("sdev-test-{}", std::process::id());
+        let session = Session::new(name.clone(), tmp.path().into(), vec![]);
+
+        assert!(session.met().unwrap().is_unmet());
+
+        Executor::default().run(&session).unwrap();
+
+        let started = tmux::Session::find_in(name.as_str(), tmp.path()).unwrap();
+        assert!(session.met().unwrap().is_met());
+
+        started.unwrap().kill().unwrap();
+    }
+
+    #[test]
+    fn session_is_laid_out_with_windows() {
+        let Some(_server) = TestServer::start() else {
+            return;
+        };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let name = format!("sdev-test-windows-{}", std::process::id());
+        let window = |name: &str, panes: &[&str]| Window {
+            name: Some(name.into()),
+            dir: None,
+            layout: None,
+            panes: panes.iter().map(|pane| pane.to_string()).collect(),
+        };
+        let session = Session::new(
+            name.clone(),
+            tmp.path().into(),
+            vec![window("editor", &[""]), window("server", &["", "C-c"])],
+        );
+
+        Executor::default().run(&session).unwrap();
+
+        let started = tmux::Session::find_in(name.as_str(), tmp.path())
+            .unwrap()
+            .unwrap();
+        let windows = started.windows().unwrap();
+        started.kill().unwrap();
+
+        assert_eq!(
+            vec![("editor", 1), ("server", 2)],
+            windows
+                .iter()
+                .map(|window| (window.name.as_str(), window.commands.len()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn session_started_elsewhere_is_not_taken_over() {
+        let Some(_server) = TestServer::start() else {
+            return;
+        };
+
+        let (repo, elsewhere) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let name = format!("sdev-test-elsewhere-{}", std::process::id());
+        let started = tmux::Session::create_in(name.as_str(), elsewhere.path(), &[]).unwrap();
+        let session = Session::new(name.clone(), repo.path().into(), vec![]);
+
+        let met = session.met();
+        let meet = session.meet();
+        started.kill().unwrap();
+
+        assert!(met.is_err());
+        assert!(meet.is_err());
+    }
+}
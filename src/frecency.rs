@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::xdg;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+struct Entry {
+    count: u32,
+    /// Seconds since the epoch of the latest selection
+    last: u64,
+}
+
+/// How often and how recently things were picked, so pickers can rank them first
+///
+/// Like zoxide, a key's score is its pick count weighted by how long ago it was last picked.
+pub struct Frecency {
+    path: PathBuf,
+    entries: HashMap<String, Entry>,
+}
+
+impl Frecency {
+    pub fn repos() -> io::Result<Self> {
+        Self::load(xdg::data_dir().join("frecency/repos"))
+    }
+
+    pub fn sessions() -> io::Result<Self> {
+        Self::load(xdg::data_dir().join("frecency/sessions"))
+    }
+
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let mut frecency = Self {
+            path,
+            entries: HashMap::new(),
+        };
+
+        let contents = match fs::read_to_string(&frecency.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(frecency),
+            Err(e) => return Err(e),
+        };
+
+        for line in contents.lines() {
+            let mut fields = line.splitn(3, ' ');
+
+            if let (Some(count), Some(last), Some(key)) =
+                (fields.next(), fields.next(), fields.next())
+                && let (Ok(count), Ok(last)) = (count.parse(), last.parse())
+            {
+                frecency.entries.insert(key.into(), Entry { count, last });
+            }
+        }
+
+        Ok(frecency)
+    }
+
+    pub fn score(&self, key: &str, now: u64) -> f64 {
+        let Some(entry) = self.entries.get(key) else {
+            return 0.0;
+        };
+
+        let weight = match now.saturating_sub(entry.last) {
+            age if age < HOUR => 4.0,
+            age if age < DAY => 2.0,
+            age if age < WEEK => 0.5,
+            _ => 0.25,
+        };
+
+        f64::from(entry.count) * weight
+    }
+
+    pub fn record(&mut self, key: &str, now: u64) {
+        let entry = self.entries.entry(key.into()).or_insert(Entry {
+            count: 0,
+            last: now,
+        });
+
+        entry.count += 1;
+        entry.last = now;
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents: String = self
+            .entries
+            .iter()
+            .map(|(key, entry)| format!("{} {} {key}\n", entry.count, entry.last))
+            .collect();
+
+        // write then rename, so a concurrent load never sees a partial store
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+/// Seconds since the epoch, the unit `Frecency` works in
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_picks_outrank_frequent_old_ones() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut frecency = Frecency::load(tmp.path().join("repos")).unwrap();
+        let now = 10 * WEEK;
+
+        for _ in 0..4 {
+            frecency.record("/src/old", now - 2 * WEEK);
+        }
+        frecency.record("/src/new", now - 10);
+        frecency.record("/src/new", now);
+
+        assert_eq!(1.0, frecency.score("/src/old", now));
+        assert_eq!(8.0, frecency.score("/src/new", now));
+        assert_eq!(0.0, frecency.score("/src/never", now));
+    }
+
+    #[test]
+    fn saved_picks_are_loaded_again() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut frecency = Frecency::load(tmp.path().join("frecency/sessions")).unwrap();
+
+        frecency.record("my session", 100);
+        frecency.save().unwrap();
+
+        let frecency = Frecency::load(tmp.path().join("frecency/sessions")).unwrap();
+
+        assert_eq!(4.0, frecency.score("my session", 100));
+    }
+}
@@ -4,11 +4,15 @@ use std::path::PathBuf;
 mod cmd;
 mod config;
 mod dep;
+mod frecency;
+mod recipe;
 mod repo;
 mod shell;
 mod ui;
+mod xdg;
 
-use crate::config::Config;
+use crate::config::{Config, Protocol};
+use crate::dep::git::Layout;
 use crate::repo::GitRepoSource;
 
 #[derive(Parser)]
@@ -20,17 +24,70 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Pick a repository and print its path, see `sdev init` to cd into it
+    Cd {
+        /// Start the picker with this search, picking the repository if it's the only match
+        query: Option<String>,
+    },
     /// Clone a git repository into a standardized path
-    Clone { repo: GitRepoSource },
+    Clone {
+        repo: GitRepoSource,
+        /// Clone over HTTPS, authenticating with git credential helpers
+        #[arg(long, conflicts_with = "ssh")]
+        https: bool,
+        /// Clone over SSH
+        #[arg(long)]
+        ssh: bool,
+        /// Clone into a bare repository with a worktree per branch
+        #[arg(long)]
+        worktrees: bool,
+        /// Print what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print a shell function that makes `sdev cd` change directory
+    Init {
+        #[arg(value_enum)]
+        shell: cmd::init::Shell,
+    },
+    /// Print the repositories under the source root
+    #[command(alias("ls"))]
+    List {
+        /// Print absolute paths
+        #[arg(long)]
+        absolute: bool,
+        /// Print a JSON object per repository with its remotes and current branch
+        #[arg(long, conflicts_with = "absolute")]
+        json: bool,
+    },
     /// Open a link for the current repository
     #[command(alias("o"))]
     Open(OpenArgs),
+    /// Run the setup recipe (.sdev.toml) of a repository
+    Setup {
+        path: Option<PathBuf>,
+        /// Print what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Fuzzy attach to a repository's tmux session (creating it if necessary)
     #[command(alias("t"))]
     Tmux {
-        #[arg(long, default_value_t = cmd::tmux::Mode::Repos, value_enum)]
+        #[arg(long, default_value_t = cmd::tmux::Mode::All, value_enum)]
         mode: cmd::tmux::Mode,
+        /// Start with this search
+        #[arg(short, long)]
+        query: Option<String>,
+        /// Attach without showing the picker when only one entry matches
+        #[arg(short = '1', long)]
+        select_1: bool,
+        /// Exit with an error without showing the picker when nothing matches
+        #[arg(short = '0', long)]
+        exit_0: bool,
     },
+    /// Add, remove or fuzzy switch between worktrees of the current repository
+    #[command(alias("wt"))]
+    Worktree(WorktreeArgs),
 }
 
 #[derive(Debug, Args)]
@@ -47,24 +104,93 @@ enum OpenCommands {
     Pr { target: Option<String> },
 }
 
+#[derive(Debug, Args)]
+struct WorktreeArgs {
+    /// Without a subcommand, pick a worktree and attach its tmux session
+    #[command(subcommand)]
+    command: Option<WorktreeCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+enum WorktreeCommands {
+    /// Add a worktree for a branch, creating the branch if necessary
+    Add {
+        branch: String,
+        /// Print what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove the worktree of a branch
+    Rm {
+        branch: String,
+        /// Print what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    let config = Config {
-        host: "github.com".to_string(),
-        root: std::env::home_dir()
-            .expect("unknown HOME directory")
-            .join("src"),
-        user: "skipkayhil".to_string(),
-    };
+    let config = Config::default();
 
     match &cli.command {
-        Commands::Clone { repo } => cmd::clone::run(repo, &config),
+        Commands::Cd { query } => cmd::cd::run(query, &config),
+        Commands::Clone {
+            repo,
+            https,
+            ssh,
+            worktrees,
+            dry_run,
+        } => {
+            let protocol = match (https, ssh) {
+                (true, _) => Protocol::Https,
+                (_, true) => Protocol::Ssh,
+                _ => config.protocol,
+            };
+            let layout = if *worktrees {
+                Layout::Worktrees
+            } else {
+                config.layout
+            };
+
+            let config = Config {
+                protocol,
+                layout,
+                ..config
+            };
+
+            cmd::clone::run(repo, &config, *dry_run)
+        }
+        Commands::Init { shell } => {
+            cmd::init::run(shell);
+            Ok(())
+        }
+        Commands::List { absolute, json } => cmd::list::run(*absolute, *json, &config),
         Commands::Open(open) => match &open.command {
             OpenCommands::File { path } => cmd::open::file::run(path),
             OpenCommands::Pr { target } => cmd::open::pr::run(target),
         },
-        Commands::Tmux { mode } => cmd::tmux::run(mode, config),
+        Commands::Setup { path, dry_run } => cmd::setup::run(path, *dry_run),
+        Commands::Tmux {
+            mode,
+            query,
+            select_1,
+            exit_0,
+        } => {
+            let options = cmd::tmux::Options {
+                query: query.clone(),
+                select_1: *select_1,
+                exit_0: *exit_0,
+            };
+
+            cmd::tmux::run(mode, &options, config)
+        }
+        Commands::Worktree(worktree) => match &worktree.command {
+            Some(WorktreeCommands::Add { branch, dry_run }) => cmd::worktree::add(branch, *dry_run),
+            Some(WorktreeCommands::Rm { branch, dry_run }) => cmd::worktree::rm(branch, *dry_run),
+            None => cmd::worktree::pick(&config),
+        },
     }
 }
 
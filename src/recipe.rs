@@ -0,0 +1,483 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, bail};
+use gix::Url;
+use serde::Deserialize;
+
+use crate::dep::command::{Check, OnPath, Run};
+use crate::dep::{self, Dep, MeetResult, MetResult, Reqs, Status};
+use crate::shell::tmux::layout::Window;
+
+pub mod trust;
+
+pub const FILE_NAME: &str = ".sdev.toml";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecipeFile {
+    #[serde(default)]
+    setup: Vec<Step>,
+    /// The windows of the repository's tmux session, when sdev starts it
+    #[serde(default, rename = "window")]
+    windows: Vec<Window>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Step {
+    Run(RunStep),
+    Dir(DirStep),
+    Symlink(SymlinkStep),
+    File(FileStep),
+    Block(BlockStep),
+    Remote(RemoteStep),
+    GitConfig(GitConfigStep),
+    Command(CommandStep),
+    TmuxSession(TmuxSessionStep),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunStep {
+    run: String,
+    /// A command that succeeds once the step is done
+    met: Option<String>,
+    /// A path that exists once the step is done
+    creates: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DirStep {
+    dir: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SymlinkStep {
+    symlink: PathBuf,
+    target: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileStep {
+    file: PathBuf,
+    contents: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockStep {
+    file: PathBuf,
+    block: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemoteStep {
+    remote: String,
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GitConfigStep {
+    git_config: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandStep {
+    command: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TmuxSessionStep {
+    tmux_session: String,
+}
+
+impl Step {
+    /// Paths in a step are relative to the repository, except symlink targets which are relative
+    /// to the link like `ln -s`
+    ///
+    /// A tmux session is started with the recipe's `windows`.
+    fn dep(&self, repo: &Path, windows: &[Window]) -> anyhow::Result<Box<dyn Dep>> {
+        Ok(match self {
+            Step::Run(step) => {
+                let check = match (&step.met, &step.creates) {
+                    (Some(command), None) => Check::Succeeds(command.clone()),
+                    (None, Some(path)) => Check::Exists(path.clone()),
+                    _ => bail!(
+                        "step `run = \"{}\"` needs exactly one of `met` or `creates`",
+                        step.run
+                    ),
+                };
+
+                Box::new(Run::new(repo.into(), step.run.clone(), check))
+            }
+            Step::Dir(step) => Box::new(dep::fs::Dir::new(join_inside(repo, &step.dir)?)),
+            Step::Symlink(step) => Box::new(dep::fs::Symlink::new(
+                join_inside(repo, &step.symlink)?,
+                expand_home(&step.target),
+            )),
+            Step::File(step) => Box::new(dep::fs::File::new(
+                join_inside(repo, &step.file)?,
+                step.contents.clone(),
+            )),
+            Step::Block(step) => Box::new(dep::fs::Block::new(
+                join_inside(repo, &step.file)?,
+                step.block.clone(),
+            )),
+            Step::Remote(step) => Box::new(dep::git::Remote::new(
+                repo.into(),
+                step.remote.clone(),
+                Url::try_from(step.url.as_str())
+                    .with_context(|| format!("invalid url for remote {}", step.remote))?,
+            )),
+            Step::GitConfig(step) => Box::new(dep::git::ConfigValue::new(
+                repo.into(),
+                step.git_config.clone(),
+                step.value.clone(),
+            )),
+            Step::Command(step) => Box::new(OnPath::new(&step.command)),
+            Step::TmuxSession(step) => Box::new(dep::tmux::Session::new(
+                step.tmux_session.clone(),
+                repo.into(),
+                windows.to_vec(),
+            )),
+        })
+    }
+}
+
+/// Joins a path from a recipe to the repository, refusing ones that could leave it
+pub fn join_inside(repo: &Path, path: &Path) -> anyhow::Result<PathBuf> {
+    if path.as_os_str().is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        bail!(
+            "`{}` must be a relative path inside the repository",
+            path.display()
+        )
+    }
+
+    Ok(repo.join(path))
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => std::env::home_dir()
+            .expect("unknown HOME directory")
+            .join(rest),
+        Err(_) => path.into(),
+    }
+}
+
+/// A repository's `.sdev.toml`, declaring how to set up a fresh clone and its tmux session
+pub struct Recipe {
+    contents: String,
+    steps: Arc<[Step]>,
+    windows: Arc<[Window]>,
+}
+
+impl Recipe {
+    pub fn load(repo: &Path) -> anyhow::Result<Option<Self>> {
+        let path = repo.join(FILE_NAME);
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+
+        Self::parse(contents, repo)
+            .with_context(|| format!("invalid recipe {}", path.display()))
+            .map(Some)
+    }
+
+    fn parse(contents: String, repo: &Path) -> anyhow::Result<Self> {
+        let file: RecipeFile = toml::from_str(&contents)?;
+
+        // building every dep up front surfaces invalid steps before anything runs
+        for step in &file.setup {
+            step.dep(repo, &file.windows)?;
+        }
+
+        for window in &file.windows {
+            if let Some(dir) = &window.dir {
+                join_inside(repo, dir)?;
+            }
+        }
+
+        Ok(Self {
+            contents,
+            steps: file.setup.into(),
+            windows: file.windows.into(),
+        })
+    }
+
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    pub fn windows(&self) -> &[Window] {
+        &self.windows
+    }
+
+    pub fn setup(&self, repo: &Path) -> Setup {
+        Setup {
+            repo: repo.into(),
+            steps: self.steps.clone(),
+            windows: self.windows.clone(),
+        }
+    }
+}
+
+/// Meets every step of a recipe in order
+pub struct Setup {
+    repo: PathBuf,
+    steps: Arc<[Step]>,
+    windows: Arc<[Window]>,
+}
+
+impl Setup {
+    fn steps(&self) -> impl Iterator<Item = Sequenced> {
+        (0..self.steps.len()).map(|index| Sequenced::new(self, index))
+    }
+}
+
+impl Dep for Setup {
+    fn name(&self) -> String {
+        format!("recipe::Setup({})", self.repo.display())
+    }
+
+    fn describe(&self) -> String {
+        format!("set up {}", self.repo.display())
+    }
+
+    fn met(&self) -> MetResult {
+        // a step that can't be checked yet, like one missing a requirement, counts as unmet here
+        // and fails with its own error once it's processed
+        Ok(self
+            .steps()
+            .all(|step| matches!(step.met(), Ok(Status::Met)))
+            .into())
+    }
+
+    fn meet(&self) -> MeetResult {
+        Ok(())
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        self.steps()
+            .map(|step| Box::new(step) as Box<dyn Dep>)
+            .collect()
+    }
+
+    fn reqs_in_order(&self) -> bool {
+        true
+    }
+}
+
+/// A recipe step, keyed by its position so identical steps are still run where they're declared
+struct Sequenced {
+    index: usize,
+    inner: Box<dyn Dep>,
+}
+
+impl Sequenced {
+    fn new(setup: &Setup, index: usize) -> Self {
+        Self {
+            index,
+            inner: setup.steps[index]
+                .dep(&setup.repo, &setup.windows)
+                .expect("steps are validated when the recipe is loaded"),
+        }
+    }
+}
+
+impl Dep for Sequenced {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn key(&self) -> String {
+        format!("{}#{}", self.inner.key(), self.index)
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn met(&self) -> MetResult {
+        self.inner.met()
+    }
+
+    fn meet(&self) -> MeetResult {
+        self.inner.meet()
+    }
+
+    fn reqs_to_met(&self) -> Reqs {
+        self.inner.reqs_to_met()
+    }
+
+    fn reqs_to_meet(&self) -> Reqs {
+        self.inner.reqs_to_meet()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::executor::Executor;
+    use crate::dep::plan::Plan;
+
+    #[test]
+    fn runs_steps_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let recipe = Recipe::parse(
+            r#"
+            [[setup]]
+            dir = "log"
+
+            [[setup]]
+            run = "echo one >> log/order && touch log/one"
+            creates = "log/one"
+
+            [[setup]]
+            run = "echo two >> log/order"
+            met = "grep -q two log/order"
+            "#
+            .into(),
+            tmp.path(),
+        )
+        .unwrap();
+
+        Executor::new(4).run(&recipe.setup(tmp.path())).unwrap();
+
+        assert_eq!(
+            "one\ntwo\n",
+            fs::read_to_string(tmp.path().join("log/order")).unwrap()
+        );
+    }
+
+    #[test]
+    fn plan_lists_unmet_steps_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let recipe = Recipe::parse(
+            r#"
+            [[setup]]
+            run = "true"
+            met = "true"
+
+            [[setup]]
+            run = "touch one"
+            creates = "one"
+
+            [[setup]]
+            run = "touch two"
+            creates = "two"
+            "#
+            .into(),
+            tmp.path(),
+        )
+        .unwrap();
+
+        let plan = Plan::new(&recipe.setup(tmp.path())).to_string();
+        let dir = tmp.path().display();
+
+        assert_eq!(
+            format!(
+                "✗ set up {dir}\n  ✓ run `true` in {dir}\n  ✗ run `touch one` in {dir}\n  ✗ run `touch two` in {dir}\n"
+            ),
+            plan
+        );
+    }
+
+    #[test]
+    fn errors_on_run_steps_without_a_check() {
+        let error = Recipe::parse("[[setup]]\nrun = \"make\"".into(), Path::new("/repo"))
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            "step `run = \"make\"` needs exactly one of `met` or `creates`",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn errors_on_unknown_steps() {
+        let result = Recipe::parse("[[setup]]\nmkdir = \"log\"".into(), Path::new("/repo"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn errors_on_paths_outside_the_repository() {
+        for path in ["/etc/profile", "../other", "log/../../other", "./"] {
+            let error = Recipe::parse(
+                format!("[[setup]]\nfile = \"{path}\"\ncontents = \"\""),
+                Path::new("/repo"),
+            )
+            .err()
+            .unwrap();
+
+            assert_eq!(
+                format!("`{path}` must be a relative path inside the repository"),
+                error.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn parses_session_windows() {
+        let recipe = Recipe::parse(
+            r#"
+            [[window]]
+            name = "editor"
+            panes = ["nvim"]
+
+            [[window]]
+            dir = "web"
+            layout = "even-horizontal"
+            panes = ["", "npm run dev"]
+            "#
+            .into(),
+            Path::new("/repo"),
+        )
+        .unwrap();
+
+        let windows = recipe.windows();
+
+        assert_eq!(2, windows.len());
+        assert_eq!(Some("editor"), windows[0].name.as_deref());
+        assert_eq!(Some(Path::new("web")), windows[1].dir.as_deref());
+        assert_eq!(vec!["", "npm run dev"], windows[1].panes);
+        assert!(recipe.setup(Path::new("/repo")).reqs_to_meet().is_empty());
+    }
+
+    #[test]
+    fn errors_on_window_dirs_outside_the_repository() {
+        let result = Recipe::parse("[[window]]\ndir = \"../other\"".into(), Path::new("/repo"));
+
+        assert_eq!(
+            "`../other` must be a relative path inside the repository",
+            result.err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn missing_recipe_is_none() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        assert!(Recipe::load(tmp.path()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::xdg;
+
+/// Recipes the user has agreed to run, keyed by repository path and a hash of the recipe
+///
+/// Any change to a recipe changes its hash, so edited recipes have to be trusted again.
+pub struct TrustStore {
+    path: PathBuf,
+}
+
+impl Default for TrustStore {
+    fn default() -> Self {
+        Self::new(xdg::data_dir().join("trusted_recipes"))
+    }
+}
+
+impl TrustStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn is_trusted(&self, repo: &Path, recipe: &str) -> io::Result<bool> {
+        let entries = match fs::read_to_string(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        let entry = entry(repo, recipe);
+
+        Ok(entries.lines().any(|line| line == entry))
+    }
+
+    pub fn trust(&self, repo: &Path, recipe: &str) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        writeln!(file, "{}", entry(repo, recipe))
+    }
+}
+
+fn entry(repo: &Path, recipe: &str) -> String {
+    let hash = gix::objs::compute_hash(
+        gix::hash::Kind::Sha1,
+        gix::objs::Kind::Blob,
+        recipe.as_bytes(),
+    )
+    .expect("sha1 hashing doesn't fail");
+
+    format!("{hash} {}", repo.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrustStore;
+    use std::path::Path;
+
+    #[test]
+    fn trusts_recipes_until_they_change() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = TrustStore::new(tmp.path().join("sdev/trusted_recipes"));
+        let repo = Path::new("/src/github.com/rails/rails");
+
+        assert!(!store.is_trusted(repo, "[[setup]]").unwrap());
+
+        store.trust(repo, "[[setup]]").unwrap();
+
+        assert!(store.is_trusted(repo, "[[setup]]").unwrap());
+        assert!(!store.is_trusted(repo, "[[setup]]\n").unwrap());
+        assert!(
+            !store
+                .is_trusted(Path::new("/src/github.com/me/rails"), "[[setup]]")
+                .unwrap()
+        );
+    }
+}
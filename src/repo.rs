@@ -1,24 +1,83 @@
 use bstr::ByteSlice;
+use gix::discover::repository::Kind;
 use gix::url::{Scheme, Url};
 
 use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
 
+use crate::dep::git::BARE_DIR;
+
+pub mod discovery;
+pub mod index;
+pub mod probe;
+pub mod rewrite;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoKind {
+    /// A repository with its `.git` directory inside the worktree
+    Normal,
+    /// A repository without a worktree of its own, including clones with the worktrees layout
+    Bare,
+    /// A linked worktree of another repository
+    Worktree,
+    /// A submodule checked out inside another repository
+    Submodule,
+}
+
+impl RepoKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RepoKind::Normal => "normal",
+            RepoKind::Bare => "bare",
+            RepoKind::Worktree => "worktree",
+            RepoKind::Submodule => "submodule",
+        }
+    }
+}
+
+impl FromStr for RepoKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            RepoKind::Normal,
+            RepoKind::Bare,
+            RepoKind::Worktree,
+            RepoKind::Submodule,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == s)
+        .ok_or(format!("unknown repo kind: {s}"))
+    }
+}
+
 #[derive(Clone)]
 pub struct GitRepo {
     name: String,
     path: PathBuf,
+    kind: RepoKind,
 }
 
 impl GitRepo {
-    pub fn new(name: String, path: PathBuf) -> Self {
-        Self { name, path }
+    pub fn new(name: String, path: PathBuf, kind: RepoKind) -> Self {
+        Self { name, path, kind }
+    }
+
+    /// A repository named after its directory
+    pub fn at(path: &Path, kind: RepoKind) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+
+        Some(Self::new(name.into(), path.into(), kind))
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn kind(&self) -> RepoKind {
+        self.kind
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
@@ -28,10 +87,78 @@ impl GitRepo {
     }
 }
 
+/// What kind of repository `dir` is the top of, if any
+pub fn classify(dir: &Path) -> Option<RepoKind> {
+    let git = dir.join(".git");
+
+    if git.is_dir() {
+        return Some(RepoKind::Normal);
+    }
+
+    if git.is_file() {
+        // the worktrees layout's `.git` file points at a bare repository next to it
+        if dir.join(BARE_DIR).is_dir() {
+            return Some(RepoKind::Bare);
+        }
+
+        return match gix::discover::is_git(&git).ok()? {
+            Kind::WorkTree {
+                linked_git_dir: Some(_),
+            } => Some(RepoKind::Worktree),
+            Kind::Submodule { .. } => Some(RepoKind::Submodule),
+            _ => None,
+        };
+    }
+
+    // checking for HEAD first saves gix the work for the many directories that aren't repos
+    if dir.join("HEAD").is_file() && gix::discover::is_git(dir).is_ok_and(|kind| kind.is_bare()) {
+        return Some(RepoKind::Bare);
+    }
+
+    None
+}
+
+/// Where the checked out submodules of the repository at `dir` are
+pub fn submodules(dir: &Path) -> Vec<PathBuf> {
+    if !dir.join(".gitmodules").is_file() {
+        return vec![];
+    }
+
+    let Ok(repo) = gix::open(dir) else {
+        return vec![];
+    };
+
+    let Ok(Some(submodules)) = repo.submodules() else {
+        return vec![];
+    };
+
+    submodules
+        .filter_map(|submodule| {
+            submodule
+                .path()
+                .ok()
+                .map(|path| dir.join(path.to_path_lossy()))
+        })
+        .filter(|path| path.join(".git").exists())
+        .collect()
+}
+
+/// Shorthand prefixes for the forges sdev knows about, like `gh:owner/repo`
+const FORGES: [(&str, &str); 3] = [
+    ("gh", "github.com"),
+    ("gl", "gitlab.com"),
+    ("sr", "git.sr.ht"),
+];
+
 #[derive(Clone)]
 pub enum GitRepoSource {
     Name(String),
     Path(String),
+    /// A path on a specific host, like `gitlab.com/group/repo` or `gl:group/repo`
+    HostPath {
+        host: String,
+        path: String,
+    },
     Url {
         url: Url,
         host: String,
@@ -43,16 +170,39 @@ impl FromStr for GitRepoSource {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((host, path)) = FORGES.iter().find_map(|(prefix, host)| {
+            s.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(|path| (host, path))
+        }) {
+            let components = normal_components(path).ok_or(format!("invalid repo: {s}"))?;
+
+            if components.len() < 2 {
+                return Err(format!("invalid repo: {s}"));
+            }
+
+            return Ok(GitRepoSource::HostPath {
+                host: host.to_string(),
+                path: components.join("/"),
+            });
+        }
+
         let url = Url::try_from(s).map_err(|e| e.to_string())?;
 
         match url.scheme {
             Scheme::File => {
-                let path = url.path.to_path().map_err(|e| e.to_string())?;
-
-                for component in path.components() {
-                    let Component::Normal(..) = component else {
-                        return Err(format!("invalid repo: {s}"));
-                    };
+                let path = url.path.to_str().map_err(|e| e.to_string())?;
+                let components = normal_components(path).ok_or(format!("invalid repo: {s}"))?;
+
+                // owner/repo stays on the default host, but a leading domain needs a path after it
+                if let [host, path @ ..] = components.as_slice()
+                    && host.contains('.')
+                    && path.len() >= 2
+                {
+                    return Ok(GitRepoSource::HostPath {
+                        host: host.to_string(),
+                        path: path.join("/"),
+                    });
                 }
 
                 if s.contains('/') {
@@ -76,6 +226,79 @@ impl FromStr for GitRepoSource {
     }
 }
 
+/// The components of a relative path with any `.git` suffix removed, or `None` if it tries to
+/// escape with `..`, `.` or a leading `/`
+fn normal_components(path: &str) -> Option<Vec<&str>> {
+    let path = Path::new(path.strip_suffix(".git").unwrap_or(path));
+
+    path.components()
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod classify_tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=sdev", "-c", "user.email=sdev@example.com"])
+            .args(["-c", "protocol.file.allow=always"])
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn classifies_each_kind_of_repository() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        fs::create_dir(dir.join("lib")).unwrap();
+        git(&dir.join("lib"), &["init", "-q"]);
+        git(
+            &dir.join("lib"),
+            &["commit", "-q", "--allow-empty", "-m", "init"],
+        );
+        git(dir, &["clone", "-q", "--bare", "lib", "lib.git"]);
+
+        fs::create_dir(dir.join("app")).unwrap();
+        git(&dir.join("app"), &["init", "-q"]);
+        git(
+            &dir.join("app"),
+            &["submodule", "add", "-q", "../lib.git", "vendor/lib"],
+        );
+        git(&dir.join("app"), &["commit", "-q", "-m", "init"]);
+        git(
+            &dir.join("app"),
+            &["worktree", "add", "-q", "../app@feature"],
+        );
+
+        assert_eq!(Some(RepoKind::Normal), classify(&dir.join("app")));
+        assert_eq!(Some(RepoKind::Bare), classify(&dir.join("lib.git")));
+        assert_eq!(Some(RepoKind::Worktree), classify(&dir.join("app@feature")));
+        assert_eq!(
+            Some(RepoKind::Submodule),
+            classify(&dir.join("app/vendor/lib"))
+        );
+        assert_eq!(None, classify(&dir.join("app/vendor")));
+        assert_eq!(
+            vec![dir.join("app/vendor/lib")],
+            submodules(&dir.join("app"))
+        );
+    }
+}
+
 #[cfg(test)]
 mod git_repo_source_tests {
     use super::GitRepoSource;
@@ -111,6 +334,65 @@ mod git_repo_source_tests {
         assert_eq!("invalid repo: ../evil", result.err().unwrap());
     }
 
+    #[test]
+    fn parse_host_path() {
+        let repo: GitRepoSource = "gitlab.com/group/sub/repo".parse().unwrap();
+
+        assert!(matches!(
+            repo,
+            GitRepoSource::HostPath { host, path } if host == "gitlab.com" && path == "group/sub/repo"
+        ));
+    }
+
+    #[test]
+    fn parse_dotted_owner_as_path() {
+        let repo: GitRepoSource = "skipkayhil.dev/sdev".parse().unwrap();
+
+        assert!(matches!(repo, GitRepoSource::Path(r) if r == "skipkayhil.dev/sdev"));
+    }
+
+    #[test]
+    fn parse_forge_shorthands() {
+        for (raw, expected_host, expected_path) in [
+            ("gh:rails/rails", "github.com", "rails/rails"),
+            ("gl:group/sub/repo.git", "gitlab.com", "group/sub/repo"),
+            ("sr:~sircmpwn/scdoc", "git.sr.ht", "~sircmpwn/scdoc"),
+        ] {
+            let repo: GitRepoSource = raw.parse().unwrap();
+
+            assert!(
+                matches!(
+                    &repo,
+                    GitRepoSource::HostPath { host, path }
+                        if host == expected_host && path == expected_path
+                ),
+                "{raw} parsed to the wrong host or path"
+            );
+        }
+    }
+
+    #[test]
+    fn errors_on_host_path_traversal() {
+        for raw in [
+            "gitlab.com/../../evil",
+            "gh:../evil",
+            "gh:rails/../../evil",
+            "gl:/etc/passwd",
+            "sr:./~user/repo",
+        ] {
+            let result = raw.parse::<GitRepoSource>();
+
+            assert_eq!(Some(format!("invalid repo: {raw}")), result.err());
+        }
+    }
+
+    #[test]
+    fn errors_on_forge_shorthand_without_owner() {
+        let result = "gh:rails".parse::<GitRepoSource>();
+
+        assert_eq!("invalid repo: gh:rails", result.err().unwrap());
+    }
+
     #[test]
     fn parse_http_url() {
         let repo: GitRepoSource = "https://github.com/skipkayhil/sdev.git".parse().unwrap();
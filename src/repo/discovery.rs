@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::Config;
+
+/// Patterns in this file at the root are ignored like `Config::ignore`, one per line
+pub const IGNORE_FILE: &str = ".sdevignore";
+
+/// Which directories under a root are searched for repositories
+///
+/// An ignore pattern matches a directory's path relative to the root, or just its name when the
+/// pattern has no `/`, so `node_modules` prunes every directory with that name that isn't a
+/// repository.
+pub struct Discovery {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    patterns: Vec<String>,
+    ignore: GlobSet,
+}
+
+impl Discovery {
+    pub fn new(
+        root: PathBuf,
+        max_depth: Option<usize>,
+        patterns: Vec<String>,
+    ) -> Result<Self, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+
+        for pattern in &patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+
+        Ok(Self {
+            root,
+            max_depth,
+            patterns,
+            ignore: builder.build()?,
+        })
+    }
+
+    /// Discovery under `config.root` with its limits and any patterns from the root's ignore file
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut patterns = config.ignore.clone();
+        patterns.extend(read_ignore_file(&config.root.join(IGNORE_FILE))?);
+
+        Ok(Self::new(config.root.clone(), config.max_depth, patterns)?)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether `dir`, somewhere under the root, should be searched when it isn't a repository
+    pub fn descends_into(&self, dir: &Path) -> bool {
+        self.reaches(dir) && !self.ignores(dir)
+    }
+
+    /// Whether `dir` is under the root and within the depth limit
+    pub fn reaches(&self, dir: &Path) -> bool {
+        let Ok(relative) = dir.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        self.max_depth
+            .is_none_or(|max| relative.components().count() <= max)
+    }
+
+    /// Whether `dir` matches an ignore pattern, which only prunes directories that aren't
+    /// repositories themselves
+    fn ignores(&self, dir: &Path) -> bool {
+        let relative = dir.strip_prefix(&self.root).unwrap_or(dir);
+
+        self.ignore.is_match(relative)
+            || relative
+                .file_name()
+                .is_some_and(|name| self.ignore.is_match(name))
+    }
+
+    /// Identifies the rules, so results found under different ones aren't mixed up
+    pub fn fingerprint(&self) -> String {
+        let depth = self
+            .max_depth
+            .map_or_else(|| "-".to_string(), |max| max.to_string());
+
+        format!("{depth} {}", self.patterns.join("\t"))
+    }
+}
+
+fn read_ignore_file(path: &Path) -> io::Result<Vec<String>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(Into::into)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery(max_depth: Option<usize>, patterns: &[&str]) -> Discovery {
+        Discovery::new(
+            "/src".into(),
+            max_depth,
+            patterns.iter().map(|p| p.to_string()).collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn names_are_ignored_at_any_depth() {
+        let discovery = discovery(None, &["node_modules"]);
+
+        assert!(discovery.descends_into(Path::new("/src/github.com/rails")));
+        assert!(!discovery.descends_into(Path::new("/src/node_modules")));
+        assert!(!discovery.descends_into(Path::new("/src/github.com/rails/node_modules")));
+    }
+
+    #[test]
+    fn paths_are_ignored_relative_to_root() {
+        let discovery = discovery(None, &["archive/*", "**/vendor"]);
+
+        assert!(!discovery.descends_into(Path::new("/src/archive/old")));
+        assert!(discovery.descends_into(Path::new("/src/github.com/archive/old")));
+        assert!(!discovery.descends_into(Path::new("/src/github.com/rails/vendor")));
+    }
+
+    #[test]
+    fn depth_is_limited_below_root() {
+        let discovery = discovery(Some(3), &[]);
+
+        assert!(discovery.descends_into(Path::new("/src/github.com/rails/rails")));
+        assert!(!discovery.descends_into(Path::new("/src/github.com/rails/rails/sub")));
+        assert!(!discovery.descends_into(Path::new("/elsewhere")));
+    }
+
+    #[test]
+    fn ignore_file_skips_comments_and_blank_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config {
+            root: tmp.path().into(),
+            ignore: vec!["target".into()],
+            ..Config::default()
+        };
+        fs::write(tmp.path().join(IGNORE_FILE), "# scratch\n\nscratch\n").unwrap();
+
+        let discovery = Discovery::from_config(&config).unwrap();
+
+        assert!(!discovery.descends_into(&tmp.path().join("scratch")));
+        assert!(!discovery.descends_into(&tmp.path().join("target")));
+        assert!(discovery.descends_into(&tmp.path().join("github.com")));
+    }
+}
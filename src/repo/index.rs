@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::repo::discovery::Discovery;
+use crate::repo::{GitRepo, RepoKind, classify, submodules};
+use crate::xdg;
+
+/// Bumped whenever the lines of the index change, so an index written by another version of sdev
+/// is discarded as a whole instead of read in part
+const VERSION: u32 = 2;
+
+/// A repository that appeared or disappeared since the index was last refreshed
+pub enum Change {
+    Added(GitRepo),
+    Removed(PathBuf),
+}
+
+/// The repositories a `Discovery` finds, persisted so pickers can list them without scanning first
+///
+/// Besides repositories, the index records the mtime of every other directory it scanned. Adding
+/// or removing an entry changes a directory's mtime, so a refresh only has to read the
+/// directories whose mtime changed.
+pub struct Index {
+    path: PathBuf,
+    discovery: Discovery,
+    repos: BTreeMap<PathBuf, RepoKind>,
+    dirs: BTreeMap<PathBuf, u128>,
+}
+
+impl Index {
+    pub fn load(discovery: Discovery) -> io::Result<Self> {
+        Self::load_from(xdg::cache_dir().join("repos"), discovery)
+    }
+
+    /// Loads the index at `path`, starting empty if it's missing or was built in another format,
+    /// for another root or with other discovery rules
+    pub fn load_from(path: PathBuf, discovery: Discovery) -> io::Result<Self> {
+        let mut index = Self {
+            path,
+            discovery,
+            repos: BTreeMap::new(),
+            dirs: BTreeMap::new(),
+        };
+
+        let contents = match fs::read_to_string(&index.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(index),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = contents.lines();
+
+        if lines.next() != Some(&index.header()) {
+            return Ok(index);
+        }
+
+        for line in lines {
+            match line.split_once(' ') {
+                Some(("repo", entry)) => {
+                    if let Some((kind, path)) = entry.split_once(' ')
+                        && let Ok(kind) = kind.parse()
+                    {
+                        index.repos.insert(path.into(), kind);
+                    }
+                }
+                Some(("dir", entry)) => {
+                    if let Some((mtime, path)) = entry.split_once(' ')
+                        && let Ok(mtime) = mtime.parse()
+                    {
+                        index.dirs.insert(path.into(), mtime);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(index)
+    }
+
+    pub fn repos(&self) -> impl Iterator<Item = GitRepo> {
+        self.repos
+            .iter()
+            .filter_map(|(path, kind)| GitRepo::at(path, *kind))
+    }
+
+    /// Records a repository without a refresh, like one that was just cloned
+    pub fn insert(&mut self, repo: PathBuf, kind: RepoKind) {
+        self.repos.insert(repo, kind);
+    }
+
+    /// Rescans the root, reading only directories that changed since the last refresh
+    pub fn refresh(&mut self, mut on_change: impl FnMut(Change)) {
+        let old_repos = mem::take(&mut self.repos);
+        let old_dirs = mem::take(&mut self.dirs);
+
+        let mut children: HashMap<&Path, Vec<&Path>> = HashMap::new();
+        for path in old_repos.keys().chain(old_dirs.keys()) {
+            if let Some(parent) = path.parent() {
+                children.entry(parent).or_default().push(path);
+            }
+        }
+
+        let mut pending = vec![self.discovery.root().to_owned()];
+
+        while let Some(dir) = pending.pop() {
+            if let Some(kind) = classify(&dir) {
+                // submodules are found through their parent, since repos aren't scanned
+                if kind != RepoKind::Bare {
+                    pending.extend(
+                        submodules(&dir)
+                            .into_iter()
+                            .filter(|path| self.discovery.reaches(path)),
+                    );
+                }
+
+                if !old_repos.contains_key(&dir)
+                    && let Some(repo) = GitRepo::at(&dir, kind)
+                {
+                    on_change(Change::Added(repo));
+                }
+
+                self.repos.insert(dir, kind);
+                continue;
+            }
+
+            let Some(mtime) = mtime(&dir) else {
+                continue;
+            };
+
+            // ignored directories are never read, but are still checked for becoming a repo
+            if !self.discovery.descends_into(&dir) {
+                self.dirs.insert(dir, mtime);
+                continue;
+            }
+
+            if old_dirs.get(&dir) == Some(&mtime) {
+                let unchanged = children.get(dir.as_path()).into_iter().flatten();
+
+                pending.extend(
+                    unchanged
+                        .filter(|child| self.discovery.reaches(child))
+                        .map(|child| child.to_path_buf()),
+                );
+            } else if let Ok(entries) = fs::read_dir(&dir) {
+                pending.extend(
+                    entries
+                        .flatten()
+                        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                        .map(|entry| entry.path())
+                        .filter(|path| self.discovery.reaches(path)),
+                );
+            }
+
+            self.dirs.insert(dir, mtime);
+        }
+
+        for removed in old_repos.keys() {
+            if !self.repos.contains_key(removed) {
+                on_change(Change::Removed(removed.clone()));
+            }
+        }
+    }
+
+    /// Identifies the format, root and rules an index was built with
+    fn header(&self) -> String {
+        format!(
+            "v{VERSION} root {} {}",
+            self.discovery.root().display(),
+            self.discovery.fingerprint()
+        )
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = format!("{}\n", self.header());
+
+        for (repo, kind) in &self.repos {
+            contents.push_str(&format!("repo {} {}\n", kind.as_str(), repo.display()));
+        }
+
+        for (dir, mtime) in &self.dirs {
+            contents.push_str(&format!("dir {mtime} {}\n", dir.display()));
+        }
+
+        // write then rename, so a concurrent load never sees a partial index
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+fn mtime(dir: &Path) -> Option<u128> {
+    let modified = fs::symlink_metadata(dir).ok()?.modified().ok()?;
+
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(path: PathBuf, root: &Path, patterns: &[&str]) -> Index {
+        let patterns = patterns.iter().map(|p| p.to_string()).collect();
+
+        Index::load_from(path, Discovery::new(root.into(), None, patterns).unwrap()).unwrap()
+    }
+
+    fn refresh(index: &mut Index) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let (mut added, mut removed) = (vec![], vec![]);
+
+        index.refresh(|change| match change {
+            Change::Added(repo) => added.push(repo.path().to_owned()),
+            Change::Removed(path) => removed.push(path),
+        });
+
+        (added, removed)
+    }
+
+    #[test]
+    fn refresh_reports_added_and_removed_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("src");
+        fs::create_dir_all(root.join("github.com/rails/rails/.git")).unwrap();
+        fs::create_dir_all(root.join("github.com/rails/rails/vendor/gem/.git")).unwrap();
+
+        let mut index = load(tmp.path().join("repos"), &root, &[]);
+
+        assert_eq!(
+            (vec![root.join("github.com/rails/rails")], vec![]),
+            refresh(&mut index)
+        );
+
+        fs::create_dir_all(root.join("github.com/ruby/ruby/.git")).unwrap();
+        fs::remove_dir_all(root.join("github.com/rails")).unwrap();
+
+        assert_eq!(
+            (
+                vec![root.join("github.com/ruby/ruby")],
+                vec![root.join("github.com/rails/rails")]
+            ),
+            refresh(&mut index)
+        );
+    }
+
+    #[test]
+    fn saved_index_is_reused_until_root_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("src");
+        fs::create_dir_all(root.join("github.com/rails/rails/.git")).unwrap();
+
+        let mut index = load(tmp.path().join("cache/repos"), &root, &[]);
+        refresh(&mut index);
+        index.save().unwrap();
+
+        let mut index = load(tmp.path().join("cache/repos"), &root, &[]);
+
+        assert_eq!(
+            vec![root.join("github.com/rails/rails")],
+            index
+                .repos()
+                .map(|repo| repo.path().to_owned())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!((vec![], vec![]), refresh(&mut index));
+
+        let other = load(tmp.path().join("cache/repos"), tmp.path(), &[]);
+
+        assert_eq!(0, other.repos().count());
+    }
+
+    #[test]
+    fn index_of_another_format_is_discarded() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("src");
+        fs::create_dir_all(root.join("github.com/rails/rails/.git")).unwrap();
+
+        let fingerprint = Discovery::new(root.clone(), None, vec![])
+            .unwrap()
+            .fingerprint();
+        fs::write(
+            tmp.path().join("repos"),
+            format!(
+                "root {} {fingerprint}\ndir 1 {}\n",
+                root.display(),
+                root.join("github.com").display()
+            ),
+        )
+        .unwrap();
+
+        let mut index = load(tmp.path().join("repos"), &root, &[]);
+
+        assert!(index.dirs.is_empty());
+        assert_eq!(
+            (vec![root.join("github.com/rails/rails")], vec![]),
+            refresh(&mut index)
+        );
+    }
+
+    #[test]
+    fn ignored_dirs_are_pruned_and_rules_invalidate_the_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("src");
+        fs::create_dir_all(root.join("github.com/rails/rails/.git")).unwrap();
+        fs::create_dir_all(root.join("scratch/demo/.git")).unwrap();
+
+        let mut index = load(tmp.path().join("repos"), &root, &["scratch"]);
+
+        assert_eq!(
+            (vec![root.join("github.com/rails/rails")], vec![]),
+            refresh(&mut index)
+        );
+        index.save().unwrap();
+
+        let index = load(tmp.path().join("repos"), &root, &[]);
+
+        assert_eq!(0, index.repos().count());
+    }
+
+    #[test]
+    fn repos_named_like_ignored_dirs_are_kept() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("src");
+        fs::create_dir_all(root.join("github.com/me/vendor/.git")).unwrap();
+        fs::create_dir_all(root.join("github.com/me/app/.git")).unwrap();
+        fs::create_dir_all(root.join("vendor/gem/.git")).unwrap();
+
+        let mut index = load(tmp.path().join("repos"), &root, &["vendor"]);
+        let (mut added, _) = refresh(&mut index);
+        added.sort();
+
+        assert_eq!(
+            vec![
+                root.join("github.com/me/app"),
+                root.join("github.com/me/vendor")
+            ],
+            added
+        );
+    }
+
+    #[test]
+    fn unchanged_dirs_are_not_read_again() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("src");
+        fs::create_dir_all(root.join("github.com/rails/rails/.git")).unwrap();
+
+        let mut index = load(tmp.path().join("repos"), &root, &[]);
+        refresh(&mut index);
+
+        // pretend github.com was scanned before ruby existed, at its current mtime
+        fs::create_dir_all(root.join("github.com/ruby/ruby/.git")).unwrap();
+        let github = root.join("github.com");
+        index.dirs.insert(github.clone(), mtime(&github).unwrap());
+        index
+            .dirs
+            .retain(|dir, _| !dir.starts_with(github.join("ruby")));
+
+        assert_eq!((vec![], vec![]), refresh(&mut index));
+    }
+}
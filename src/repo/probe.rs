@@ -0,0 +1,84 @@
+use gix::Url;
+use gix::credentials;
+use gix::progress::Discard;
+use gix::protocol::handshake;
+use gix::protocol::transport::Service;
+use gix::protocol::transport::client::blocking_io::connect::connect;
+
+/// What a handshake with a remote says about a repository
+#[derive(Debug, PartialEq)]
+pub enum Probe {
+    Found,
+    Missing,
+    /// The remote asked for credentials, which hosts like GitHub do both for private
+    /// repositories and for ones that don't exist
+    Unknown,
+}
+
+/// Checks whether a repository can be fetched from `url` with a handshake like `git ls-remote`
+///
+/// Remotes that ask for credentials aren't given any, so probing never prompts.
+pub fn check(url: &Url) -> Probe {
+    let Ok(transport) = connect(url.clone(), Default::default()) else {
+        return Probe::Missing;
+    };
+
+    match gix::protocol::handshake(
+        transport,
+        Service::UploadPack,
+        no_credentials,
+        vec![],
+        &mut Discard,
+    ) {
+        Ok(_) => Probe::Found,
+        Err(e) => classify(&e),
+    }
+}
+
+// the result type is gix's, not ours to shrink
+#[allow(clippy::result_large_err)]
+fn no_credentials(_: credentials::helper::Action) -> credentials::protocol::Result {
+    Ok(None)
+}
+
+fn classify(error: &handshake::Error) -> Probe {
+    match error {
+        handshake::Error::Credentials(_)
+        | handshake::Error::EmptyCredentials
+        | handshake::Error::InvalidCredentials { .. } => Probe::Unknown,
+        _ => Probe::Missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn finds_existing_repositories_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(tmp.path())
+            .args(["init", "-q", "--bare", "remote.git"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let url = |name: &str| {
+            Url::try_from(format!("file://{}", tmp.path().join(name).display()).as_str()).unwrap()
+        };
+
+        assert_eq!(Probe::Found, check(&url("remote.git")));
+        assert_eq!(Probe::Missing, check(&url("missing.git")));
+    }
+
+    #[test]
+    fn requests_for_credentials_are_unknown() {
+        assert_eq!(
+            Probe::Unknown,
+            classify(&handshake::Error::EmptyCredentials)
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use bstr::ByteSlice;
+use gix::Url;
+
+/// Rewrites URLs starting with `instead_of` to start with `base`, like git's
+/// `url.<base>.insteadOf`
+#[derive(Clone)]
+pub struct Rule {
+    pub base: String,
+    pub instead_of: String,
+}
+
+/// URL rewrite rules from git config and sdev's own mirrors
+#[derive(Default)]
+pub struct Rewrites {
+    mirrors: Vec<Rule>,
+    fetch: Vec<Rule>,
+    push: Vec<Rule>,
+}
+
+impl Rewrites {
+    pub fn from_git_config(config: &gix::config::File<'_>) -> Self {
+        let mut rewrites = Self::default();
+
+        for section in config.sections_by_name("url").into_iter().flatten() {
+            let Some(base) = section.header().subsection_name() else {
+                continue;
+            };
+
+            for (key, rules) in [
+                ("insteadOf", &mut rewrites.fetch),
+                ("pushInsteadOf", &mut rewrites.push),
+            ] {
+                rules.extend(section.values(key).into_iter().map(|prefix| Rule {
+                    base: base.to_str_lossy().into_owned(),
+                    instead_of: prefix.to_str_lossy().into_owned(),
+                }));
+            }
+        }
+
+        rewrites
+    }
+
+    pub fn with_mirrors(self, mirrors: &[Rule]) -> Self {
+        Self {
+            mirrors: mirrors.to_vec(),
+            ..self
+        }
+    }
+
+    /// The URL to fetch from, preferring sdev's mirrors over git's `insteadOf`
+    pub fn fetch_url(&self, url: &Url) -> Result<Url, gix::url::parse::Error> {
+        let raw = url.to_bstring().to_string();
+
+        match rewrite(&self.mirrors, &raw).or_else(|| rewrite(&self.fetch, &raw)) {
+            Some(rewritten) => Url::try_from(rewritten.as_str()),
+            None => Ok(url.clone()),
+        }
+    }
+
+    /// The URL to push to, which only honours `pushInsteadOf`
+    pub fn push_url(&self, url: &Url) -> Result<Url, gix::url::parse::Error> {
+        match rewrite(&self.push, &url.to_bstring().to_string()) {
+            Some(rewritten) => Url::try_from(rewritten.as_str()),
+            None => Ok(url.clone()),
+        }
+    }
+}
+
+/// Applies the rule with the longest matching prefix, as git does
+fn rewrite(rules: &[Rule], url: &str) -> Option<String> {
+    rules
+        .iter()
+        .filter(|rule| url.starts_with(&rule.instead_of))
+        .max_by_key(|rule| rule.instead_of.len())
+        .map(|rule| format!("{}{}", rule.base, &url[rule.instead_of.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrites(config: &str) -> Rewrites {
+        Rewrites::from_git_config(&gix::config::File::try_from(config).unwrap())
+    }
+
+    fn url(raw: &str) -> Url {
+        Url::try_from(raw).unwrap()
+    }
+
+    #[test]
+    fn fetch_url_uses_longest_instead_of() {
+        let rewrites = rewrites(
+            r#"
+            [url "https://mirror.internal/"]
+                insteadOf = git@github.com:
+            [url "https://rails-mirror.internal/"]
+                insteadOf = git@github.com:rails/
+            "#,
+        );
+
+        assert_eq!(
+            "https://mirror.internal/ruby/ruby.git",
+            rewrites
+                .fetch_url(&url("git@github.com:ruby/ruby.git"))
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            "https://rails-mirror.internal/rails.git",
+            rewrites
+                .fetch_url(&url("git@github.com:rails/rails.git"))
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn mirrors_take_precedence_over_git_config() {
+        let rewrites = rewrites(
+            r#"
+            [url "https://mirror.internal/"]
+                insteadOf = git@github.com:
+            "#,
+        )
+        .with_mirrors(&[Rule {
+            base: "https://ci-mirror.internal/".into(),
+            instead_of: "git@github.com:".into(),
+        }]);
+
+        assert_eq!(
+            "https://ci-mirror.internal/rails/rails.git",
+            rewrites
+                .fetch_url(&url("git@github.com:rails/rails.git"))
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn push_url_only_uses_push_instead_of() {
+        let rewrites = rewrites(
+            r#"
+            [url "https://mirror.internal/"]
+                insteadOf = git@github.com:
+            [url "ssh://git@push.internal/"]
+                pushInsteadOf = git@github.com:
+            "#,
+        );
+
+        assert_eq!(
+            "ssh://git@push.internal/rails/rails.git",
+            rewrites
+                .push_url(&url("git@github.com:rails/rails.git"))
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            "https://aur.archlinux.org/google-chrome.git",
+            rewrites
+                .push_url(&url("https://aur.archlinux.org/google-chrome.git"))
+                .unwrap()
+                .to_string()
+        );
+    }
+}
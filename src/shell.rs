@@ -1,5 +1,7 @@
+use std::ffi::OsStr;
 use std::fmt;
 use std::io;
+use std::path::Path;
 use std::process::{Command, ExitStatus, Output};
 
 pub mod tmux;
@@ -33,6 +35,16 @@ impl Shell {
         Shell(command)
     }
 
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.0.arg(arg);
+        self
+    }
+
+    pub fn in_dir(mut self, dir: &Path) -> Self {
+        self.0.current_dir(dir);
+        self
+    }
+
     pub fn output(&mut self, print: bool) -> Result<Output, ShellError> {
         if print {
             println_shell!("{}\n", self);
@@ -54,6 +66,28 @@ impl Shell {
         Ok(())
     }
 
+    /// Runs without printing, failing with the command's stderr if it exits unsuccessfully
+    pub fn run_quietly(&mut self) -> Result<(), ShellError> {
+        self.capture()?;
+
+        Ok(())
+    }
+
+    /// Runs without printing and returns what the command printed, failing with its stderr if it
+    /// exits unsuccessfully
+    pub fn capture(&mut self) -> Result<String, ShellError> {
+        let output = self.output(false)?;
+
+        if !output.status.success() {
+            return Err(ShellError::Failed {
+                shell: self.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().into(),
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
     pub fn status(&mut self) -> Result<ExitStatus, ShellError> {
         self.0.status().map_err(|e| ShellError::Io {
             shell: self.to_string(),
@@ -70,6 +104,8 @@ pub enum ShellError {
         #[source]
         source: io::Error,
     },
+    #[error("`{shell}` failed: {stderr}")]
+    Failed { shell: String, stderr: String },
 }
 
 impl fmt::Display for Shell {
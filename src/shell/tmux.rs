@@ -1,10 +1,84 @@
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::bail;
 
 use crate::shell;
 
-#[derive(Clone)]
+/// `shell::new!` for tmux, on a server of its own in tests so they never touch the user's sessions
+macro_rules! tmux {
+    ($($x:expr),* $(,)?) => {
+        {
+            let mut command = std::process::Command::new("tmux");
+            #[cfg(test)]
+            command
+                .args(["-f", "/dev/null", "-S"])
+                .arg(crate::shell::tmux::TestServer::socket());
+            $(command.arg($x);)*
+            crate::shell::Shell::new(command)
+        }
+    };
+}
+
+pub mod layout;
+
+/// How the session of a repository is named after its path below the root
+#[derive(Clone, Copy, Default)]
+pub enum Naming {
+    /// `rails`
+    #[default]
+    Basename,
+    /// `rails/rails`
+    OwnerRepo,
+    /// `github.com/rails/rails`
+    Full,
+}
+
+impl Naming {
+    pub fn name(self, relative_path: &Path) -> SessionName {
+        let components: Vec<_> = relative_path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+
+        let kept = match self {
+            Naming::Basename => 1,
+            Naming::OwnerRepo => 2,
+            Naming::Full => components.len(),
+        };
+
+        components[components.len().saturating_sub(kept)..]
+            .join("/")
+            .as_str()
+            .into()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SessionName(String);
 
+impl SessionName {
+    /// Names to try for the session of a repository at `relative_path`: this one, then its owner
+    /// and repository, then its full path, leaving out empty and repeated names
+    fn candidates(self, relative_path: &Path) -> Vec<SessionName> {
+        let mut names: Vec<SessionName> = vec![];
+
+        for name in [
+            self,
+            Naming::OwnerRepo.name(relative_path),
+            Naming::Full.name(relative_path),
+        ] {
+            if !name.0.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+}
+
 impl From<&str> for SessionName {
     fn from(value: &str) -> Self {
         Self(
@@ -20,31 +94,155 @@ impl From<&str> for SessionName {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Session {
     name: SessionName,
 }
 
+/// Refers to a session by name without checking it exists, for tests that don't need a server
+#[cfg(test)]
+impl From<&str> for Session {
+    fn from(value: &str) -> Self {
+        Self { name: value.into() }
+    }
+}
+
 impl Session {
+    /// Finds the session named `name`, failing if it was started somewhere other than `path`
+    pub fn find_in<S: Into<SessionName>>(name: S, path: &Path) -> anyhow::Result<Option<Self>> {
+        let name = name.into();
+
+        match list_sessions()?
+            .into_iter()
+            .find(|info| info.session.name == name)
+        {
+            Some(info) if info.path == path => Ok(Some(info.session)),
+            Some(info) => bail!(
+                "tmux session {} was started in {}, not {}",
+                name.0,
+                info.path.display(),
+                path.display()
+            ),
+            None => Ok(None),
+        }
+    }
+
     pub fn find_or_create_in<S: Into<SessionName>>(
         name: S,
         path: &Path,
-    ) -> Result<Self, shell::ShellError> {
+        windows: &[layout::Window],
+    ) -> anyhow::Result<Self> {
         let session_name = name.into();
 
-        match has(&session_name)? {
+        match Self::find_in(session_name.clone(), path)? {
             Some(session) => Ok(session),
-            None => Ok(new_session(&session_name, path)?),
+            None => Self::create_in(session_name, path, windows),
         }
     }
 
+    /// Starts a detached session with `windows`, which should only be done for a new name
+    pub fn create_in<S: Into<SessionName>>(
+        name: S,
+        path: &Path,
+        windows: &[layout::Window],
+    ) -> anyhow::Result<Self> {
+        let name = name.into();
+
+        if name.0.is_empty() {
+            bail!("tmux session names can't be empty");
+        }
+
+        layout::create(&name.0, path, windows)?;
+
+        Ok(Session { name })
+    }
+
     pub fn attach_or_switch(&self) -> Result<(), shell::ShellError> {
-        attach_or_switch(&self.name)
+        let subcommand = if in_tmux() { CMD_SWITCH } else { CMD_ATTACH };
+
+        tmux!(subcommand, "-t", self.target()).run(false)
     }
 
     pub fn name_str(&self) -> &str {
         &self.name.0
     }
+
+    pub fn kill(&self) -> Result<(), shell::ShellError> {
+        tmux!("kill-session", "-t", self.target()).run_quietly()
+    }
+
+    pub fn rename<S: Into<SessionName>>(&self, name: S) -> Result<Self, shell::ShellError> {
+        let name = name.into();
+
+        tmux!("rename-session", "-t", self.target(), &name.0).run_quietly()?;
+
+        Ok(Session { name })
+    }
+
+    /// Detaches every client attached to the session, except the one sdev is running in
+    pub fn detach_other_clients(&self) -> Result<(), shell::ShellError> {
+        let current = current_client()?;
+        let clients =
+            tmux!("list-clients", "-t", self.target(), "-F", "#{client_name}").capture()?;
+
+        for client in clients.lines() {
+            if Some(client) != current.as_deref() {
+                tmux!("detach-client", "-t", client).run_quietly()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Targets exactly this session, instead of any session the name is a prefix of
+    fn target(&self) -> String {
+        format!("={}", self.name.0)
+    }
+
+    pub fn windows(&self) -> Result<Vec<Window>, anyhow::Error> {
+        let raw_output = tmux!(
+            "list-panes",
+            "-s",
+            "-t",
+            self.target(),
+            "-F",
+            "#{window_index}\t#{window_active}\t#{pane_current_command}\t#{window_name}"
+        )
+        .output(false)?;
+
+        let parsed_output = String::from_utf8(raw_output.stdout)?;
+        let mut windows: Vec<Window> = vec![];
+
+        for line in parsed_output.lines() {
+            let mut fields = line.splitn(4, '\t');
+            let (Some(index), Some(active), Some(command), Some(name)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            // panes are listed window by window
+            match windows.last_mut() {
+                Some(window) if window.index == index => window.commands.push(command.into()),
+                _ => windows.push(Window {
+                    index: index.into(),
+                    name: name.into(),
+                    active: active == "1",
+                    commands: vec![command.into()],
+                }),
+            }
+        }
+
+        Ok(windows)
+    }
+}
+
+pub struct Window {
+    pub index: String,
+    pub name: String,
+    pub active: bool,
+    /// The command running in each pane
+    pub commands: Vec<String>,
 }
 
 const CMD_ATTACH: &str = "attach-session";
@@ -54,40 +252,256 @@ fn in_tmux() -> bool {
     std::env::var("TMUX").is_ok()
 }
 
-fn attach_or_switch(name: &SessionName) -> Result<(), shell::ShellError> {
-    let subcommand = if in_tmux() { CMD_SWITCH } else { CMD_ATTACH };
+fn current_client() -> Result<Option<String>, shell::ShellError> {
+    if !in_tmux() {
+        return Ok(None);
+    }
+
+    let raw_output = tmux!("display-message", "-p", "#{client_name}").output(false)?;
+
+    Ok(Some(
+        String::from_utf8_lossy(&raw_output.stdout).trim().into(),
+    ))
+}
 
-    shell::new!("tmux", subcommand, "-t", &name.0).run(false)
+/// Where a repository's session is found or what a new one is named
+#[derive(Debug, PartialEq, Eq)]
+pub enum Resolved {
+    Existing(Session),
+    New(SessionName),
 }
 
-fn has(name: &SessionName) -> Result<Option<Session>, shell::ShellError> {
-    if shell::new!("tmux", "has", "-t", format!("={}", name.0))
-        // output instead of status to intercept stdout
-        .output(false)?
-        .status
-        .success()
+/// Picks the session started in `path`, or names a new one `name` unless a session was started
+/// under it, then after more of `relative_path`, then the full path numbered
+///
+/// Comparing paths keeps a repository from attaching to the session of another one with the same
+/// name. A repository at the root has no path to name a new session after, so it's an error.
+pub fn resolve(
+    name: SessionName,
+    path: &Path,
+    relative_path: &Path,
+    sessions: &[SessionInfo],
+) -> anyhow::Result<Resolved> {
+    if let Some(info) = sessions.iter().find(|info| info.path == path) {
+        return Ok(Resolved::Existing(info.session.clone()));
+    }
+
+    let full = Naming::Full.name(relative_path);
+    if full.0.is_empty() {
+        bail!(
+            "{} has no path to name a tmux session after",
+            path.display()
+        );
+    }
+
+    let taken = |name: &SessionName| sessions.iter().any(|info| &info.session.name == name);
+
+    if let Some(name) = name
+        .candidates(relative_path)
+        .into_iter()
+        .find(|name| !taken(name))
     {
-        Ok(Some(Session { name: name.clone() }))
-    } else {
-        Ok(None)
+        return Ok(Resolved::New(name));
     }
+
+    let mut n = 2;
+
+    loop {
+        let name = SessionName(format!("{}-{n}", full.0));
+
+        if !taken(&name) {
+            return Ok(Resolved::New(name));
+        }
+
+        n += 1;
+    }
+}
+
+/// A session with what `list-sessions` reports about it
+#[derive(Clone)]
+pub struct SessionInfo {
+    pub session: Session,
+    /// The directory the session was started in
+    pub path: PathBuf,
+    /// Whether any client is attached to the session
+    pub attached: bool,
 }
 
-pub fn list_sessions() -> Result<Vec<Session>, anyhow::Error> {
-    let raw_output = shell::new!("tmux", "list-sessions", "-F", "#{session_name}").output(false)?;
+pub fn list_sessions() -> Result<Vec<SessionInfo>, anyhow::Error> {
+    let raw_output = tmux!(
+        "list-sessions",
+        "-F",
+        "#{session_attached}\t#{session_path}\t#{session_name}"
+    )
+    .output(false)?;
 
     let parsed_output = String::from_utf8(raw_output.stdout)?;
 
     Ok(parsed_output
         .lines()
-        .map(|s| Session {
-            name: SessionName::from(s),
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (attached, path, name) = (fields.next()?, fields.next()?, fields.next()?);
+
+            Some(SessionInfo {
+                session: Session {
+                    name: SessionName::from(name),
+                },
+                path: path.into(),
+                attached: attached != "0",
+            })
         })
         .collect())
 }
 
-fn new_session(name: &SessionName, path: &Path) -> Result<Session, shell::ShellError> {
-    shell::new!("tmux", "new-session", "-d", "-s", &name.0, "-c", &path).run(false)?;
+/// A session as `list-sessions` would report it, for tests that don't need a server
+#[cfg(test)]
+impl SessionInfo {
+    pub fn started(name: &str, path: &str, attached: bool) -> Self {
+        Self {
+            session: Session::from(name),
+            path: path.into(),
+            attached,
+        }
+    }
+}
+
+/// Where the current test server listens, a socket that doesn't exist while no test holds one
+#[cfg(test)]
+static TEST_SOCKET: std::sync::Mutex<Option<PathBuf>> = std::sync::Mutex::new(None);
+
+/// A fresh tmux server for a test that starts sessions, killed with them once the test is done
+/// even if it panicked
+///
+/// Tests holding one run one at a time, and each gets a server of its own, so none can reach a
+/// server that's still shutting down.
+#[cfg(test)]
+pub struct TestServer {
+    _lock: std::sync::MutexGuard<'static, ()>,
+    dir: tempfile::TempDir,
+}
 
-    Ok(Session { name: name.clone() })
+#[cfg(test)]
+impl TestServer {
+    /// Waits for the previous test's server to be done, or returns `None` if tmux can't start one
+    pub fn start() -> Option<Self> {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+        let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().ok()?;
+        *TEST_SOCKET.lock().unwrap() = Some(dir.path().join("tmux"));
+        let server = Self { _lock: lock, dir };
+
+        // a server exits with its last session, so this one keeps it running during the test
+        tmux!(
+            "new-session",
+            "-d",
+            "-s",
+            "sdev-test-server",
+            "-c",
+            server.dir.path()
+        )
+        .run_quietly()
+        .ok()?;
+
+        Some(server)
+    }
+
+    fn socket() -> PathBuf {
+        TEST_SOCKET
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| "/nonexistent/sdev-test".into())
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = tmux!("kill-server").output(false);
+        *TEST_SOCKET.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_the_strategy() {
+        let path = Path::new("github.com/rails/rails.js");
+
+        assert_eq!("rails_js", Naming::Basename.name(path).0);
+        assert_eq!("rails/rails_js", Naming::OwnerRepo.name(path).0);
+        assert_eq!("github_com/rails/rails_js", Naming::Full.name(path).0);
+    }
+
+    #[test]
+    fn sessions_of_other_repos_are_not_reused() {
+        let relative = Path::new("github.com/me/rails");
+        let path = Path::new("/src/github.com/me/rails");
+        let resolve =
+            |sessions: &[SessionInfo]| resolve("rails".into(), path, relative, sessions).unwrap();
+        let started = |name, path| SessionInfo::started(name, path, false);
+
+        assert_eq!(
+            Resolved::New("me/rails".into()),
+            resolve(&[started("rails", "/src/github.com/rails/rails")])
+        );
+        assert_eq!(
+            Resolved::New("github_com/me/rails-2".into()),
+            resolve(&[
+                started("rails", "/src/github.com/rails/rails"),
+                started("me/rails", "/src/gitlab.com/me/rails"),
+                started("github_com/me/rails", "/tmp"),
+            ])
+        );
+        assert_eq!(
+            Resolved::Existing("work".into()),
+            resolve(&[started("work", "/src/github.com/me/rails")])
+        );
+    }
+
+    #[test]
+    fn repos_without_a_path_below_the_root_are_not_named() {
+        let resolved = resolve("".into(), Path::new("/src"), Path::new(""), &[]);
+
+        assert_eq!(
+            "/src has no path to name a tmux session after",
+            resolved.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn sessions_are_renamed_and_killed() {
+        let Some(_server) = TestServer::start() else {
+            return;
+        };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let name = |suffix: &str| format!("sdev-test-{}-{suffix}", std::process::id());
+        let first = Session::create_in(name("first").as_str(), tmp.path(), &[]).unwrap();
+        let second = Session::create_in(name("second").as_str(), tmp.path(), &[]).unwrap();
+
+        let renamed = first.rename(name("renamed").as_str()).unwrap();
+        let taken = renamed.rename(name("second").as_str());
+
+        assert!(
+            Session::find_in(name("first").as_str(), tmp.path())
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Session::find_in(name("renamed").as_str(), tmp.path())
+                .unwrap()
+                .is_some()
+        );
+
+        renamed.kill().unwrap();
+        second.kill().unwrap();
+
+        assert!(taken.is_err());
+        assert!(second.kill().is_err());
+    }
 }
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use anyhow::bail;
+
+use crate::recipe::join_inside;
+use crate::shell::Shell;
+
+/// A window a new session starts with, like a tmuxinator window
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Window {
+    pub name: Option<String>,
+    /// Where the window's panes start, relative to the session's directory
+    pub dir: Option<PathBuf>,
+    /// A tmux layout like `main-vertical` or `tiled`, selected once the panes are split
+    pub layout: Option<String>,
+    /// The command started in each pane, the first one unsplit; an empty command leaves a shell
+    #[serde(default)]
+    pub panes: Vec<String>,
+}
+
+impl Window {
+    /// Finishes `command` that creates the window, then splits and starts its panes
+    fn open(&self, mut command: Shell, root: &Path) -> anyhow::Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => join_inside(root, dir)?,
+            None => root.into(),
+        };
+
+        command.arg("-c").arg(&dir);
+        if let Some(name) = &self.name {
+            command.arg("-n").arg(name);
+        }
+
+        let window = id(command)?;
+
+        for (i, pane) in self.panes.iter().enumerate() {
+            let target = match i {
+                0 => window.clone(),
+                _ => id(tmux!(
+                    "split-window",
+                    "-d",
+                    "-t",
+                    &window,
+                    "-c",
+                    &dir,
+                    "-P",
+                    "-F",
+                    "#{pane_id}"
+                ))?,
+            };
+
+            // sent literally, so a command like `C-c` isn't read as a key name
+            if !pane.is_empty() {
+                tmux!("send-keys", "-t", &target, "-l", pane).run_quietly()?;
+                tmux!("send-keys", "-t", &target, "Enter").run_quietly()?;
+            }
+        }
+
+        if let Some(layout) = &self.layout {
+            tmux!("select-layout", "-t", &window, layout).run_quietly()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Starts a detached session in `root` with `windows`, or with one plain window if there are none
+pub(super) fn create(name: &str, root: &Path, windows: &[Window]) -> anyhow::Result<()> {
+    let Some((first, rest)) = windows.split_first() else {
+        return Ok(tmux!("new-session", "-d", "-s", name, "-c", root).run_quietly()?);
+    };
+
+    first.open(
+        tmux!("new-session", "-d", "-s", name, "-P", "-F", "#{window_id}"),
+        root,
+    )?;
+
+    for window in rest {
+        window.open(
+            tmux!(
+                "new-window",
+                "-d",
+                "-t",
+                format!("={name}:"),
+                "-P",
+                "-F",
+                "#{window_id}"
+            ),
+            root,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Runs a command printing the id of the window or pane it created
+///
+/// Fails rather than return an empty id, which tmux would take as its current pane.
+fn id(mut command: Shell) -> anyhow::Result<String> {
+    let id = command.capture()?.trim().to_string();
+
+    if id.is_empty() {
+        bail!("`{command}` printed no id")
+    }
+
+    Ok(id)
+}
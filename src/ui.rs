@@ -1,2 +1,3 @@
 pub mod picker;
 pub mod ratinit;
+pub mod select;
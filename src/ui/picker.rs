@@ -19,12 +19,18 @@ const PADDED_CHEVRON: &str = "> ";
 static MATCHER: LazyLock<Mutex<Matcher>> = LazyLock::new(|| Mutex::new(Matcher::default()));
 
 type FormatFn<T, D> = fn(&T, &D) -> Utf32String;
+type BiasFn<T> = Box<dyn Fn(&T) -> f64>;
+type TagFn<T> = fn(&T) -> String;
 
 pub struct Picker<T: Clone + Send + Sync + 'static, D> {
     nucleo: Nucleo<T>,
     selected: u32,
     formatter: FormatFn<T, D>,
     data: D,
+    bias: Option<BiasFn<T>>,
+    tag: Option<TagFn<T>>,
+    /// Positions in nucleo's matches in the order they're shown, when biased
+    order: Vec<u32>,
 }
 
 impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
@@ -36,9 +42,63 @@ impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
             selected: 0,
             formatter,
             data,
+            bias: None,
+            tag: None,
+            order: vec![],
         }
     }
 
+    /// Breaks ties in fuzzy score with `bias`, highest first, which also orders an empty search
+    pub fn with_bias(self, bias: impl Fn(&T) -> f64 + 'static) -> Self {
+        Self {
+            bias: Some(Box::new(bias)),
+            ..self
+        }
+    }
+
+    /// Shows `tag` after each item, like its state, without it being searched
+    pub fn with_tag(self, tag: TagFn<T>) -> Self {
+        Self {
+            tag: Some(tag),
+            ..self
+        }
+    }
+
+    /// The position in nucleo's matches of the `n`th shown item
+    fn nth(&self, n: u32) -> u32 {
+        match self.bias {
+            Some(_) => self.order.get(n as usize).copied().unwrap_or(n),
+            None => n,
+        }
+    }
+
+    fn reorder(&mut self) {
+        let Some(bias) = &self.bias else {
+            return;
+        };
+
+        let snapshot = self.nucleo.snapshot();
+        let pattern = snapshot.pattern().column_pattern(0);
+        let mut matcher = MATCHER.lock().unwrap();
+
+        let mut keys: Vec<(u32, f64, u32)> = snapshot
+            .matched_items(..)
+            .zip(0..)
+            .map(|(item, n)| {
+                let score = pattern
+                    .score(item.matcher_columns[0].slice(..), &mut matcher)
+                    .unwrap_or(0);
+
+                (score, bias(item.data), n)
+            })
+            .collect();
+
+        // nucleo's own order already breaks remaining ties
+        keys.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)).then(a.2.cmp(&b.2)));
+
+        self.order = keys.into_iter().map(|(_, _, n)| n).collect();
+    }
+
     pub fn pop_char(&mut self, search: &str) {
         self.nucleo
             .pattern
@@ -63,26 +123,27 @@ impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
         }
     }
 
-    pub fn select(&mut self, i: u32) {
-        if self.nucleo.snapshot().matched_item_count() > i {
-            self.selected = i;
-        } else {
-            self.selected = self.nucleo.snapshot().matched_item_count() - 1
-        }
-    }
-
-    pub fn selected(&self) -> u32 {
-        self.selected
-    }
-
     pub fn selected_data(&self) -> Option<T> {
         self.nucleo
             .snapshot()
-            .get_matched_item(self.selected)
+            .get_matched_item(self.nth(self.selected))
             .map(|item| item.data)
             .cloned()
     }
 
+    /// How many items match the search, out of how many in total
+    pub fn counts(&self) -> (u32, u32) {
+        let snapshot = self.nucleo.snapshot();
+
+        (snapshot.matched_item_count(), snapshot.item_count())
+    }
+
+    /// Removes every item, keeping the current search
+    pub fn clear(&mut self) {
+        self.nucleo.restart(true);
+        self.selected = 0;
+    }
+
     pub fn push(&mut self, t: T) {
         self.nucleo
             .injector()
@@ -92,6 +153,12 @@ impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
     pub fn tick(&mut self) {
         let status = self.nucleo.tick(10);
 
+        if status.changed
+            || self.order.len() != self.nucleo.snapshot().matched_item_count() as usize
+        {
+            self.reorder();
+        }
+
         if status.changed {
             self.selected = self.selected.min(
                 self.nucleo
@@ -102,6 +169,20 @@ impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
         }
     }
 
+    /// Ticks until every item has been matched against the search, for decisions that need all
+    /// the matches up front
+    pub fn settle(&mut self) {
+        while self.nucleo.tick(10).running {}
+
+        self.reorder();
+        self.selected = self.selected.min(
+            self.nucleo
+                .snapshot()
+                .matched_item_count()
+                .saturating_sub(1),
+        );
+    }
+
     pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
         self.tick();
 
@@ -120,10 +201,11 @@ impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
 
         Widget::render(block, area, buf);
 
-        let mut current_y = inner_area.bottom() - 1;
+        let mut current_y = inner_area.bottom().saturating_sub(1);
         let selected_y = current_y.saturating_sub(self.selected.try_into().unwrap_or(u16::MAX));
 
-        snap.matched_items(min_displayed..max_displayed)
+        (min_displayed..max_displayed)
+            .filter_map(|n| snap.get_matched_item(self.nth(n)))
             .for_each(|item| {
                 let matched_string = item.matcher_columns[0].slice(..);
 
@@ -154,6 +236,10 @@ impl<T: Clone + Send + Sync + 'static, D> Picker<T, D> {
                     styled_string.spans[index] = styled_string.spans[index].clone().red();
                 });
 
+                if let Some(tag) = self.tag {
+                    styled_string.spans.push(tag(item.data).dark_gray());
+                }
+
                 if current_y == selected_y {
                     let selected_indicator_rect = Rect {
                         x: 0,
@@ -23,7 +23,7 @@ use std::boxed::Box;
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::io::{self, Stdout, stdout};
+use std::io::{self, Stdout, Write, stdout};
 
 use ratatui_core::terminal::{Terminal, TerminalOptions};
 use ratatui_crossterm::CrosstermBackend;
@@ -32,14 +32,14 @@ use ratatui_crossterm::crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
 
-pub type DefaultTerminal = Terminal<CrosstermBackend<Stdout>>;
+pub type DefaultTerminal<W = Stdout> = Terminal<CrosstermBackend<W>>;
 
 pub fn init() -> DefaultTerminal {
     try_init().expect("failed to initialize terminal")
 }
 
 pub fn try_init() -> io::Result<DefaultTerminal> {
-    set_panic_hook();
+    set_panic_hook(stdout);
     enable_raw_mode()?;
     execute!(stdout(), EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout());
@@ -51,9 +51,25 @@ pub fn init_with_options(options: TerminalOptions) -> DefaultTerminal {
 }
 
 pub fn try_init_with_options(options: TerminalOptions) -> io::Result<DefaultTerminal> {
-    set_panic_hook();
+    try_init_with_options_on(stdout, options)
+}
+
+// the _on variants draw on another output, like stderr when stdout is captured by `sdev cd`
+pub fn try_init_on<W: Write + 'static>(output: fn() -> W) -> io::Result<DefaultTerminal<W>> {
+    set_panic_hook(output);
     enable_raw_mode()?;
-    let backend = CrosstermBackend::new(stdout());
+    execute!(output(), EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(output());
+    Terminal::new(backend)
+}
+
+pub fn try_init_with_options_on<W: Write + 'static>(
+    output: fn() -> W,
+    options: TerminalOptions,
+) -> io::Result<DefaultTerminal<W>> {
+    set_panic_hook(output);
+    enable_raw_mode()?;
+    let backend = CrosstermBackend::new(output());
     Terminal::with_options(backend, options)
 }
 
@@ -64,17 +80,27 @@ pub fn restore() {
 }
 
 pub fn try_restore() -> io::Result<()> {
+    try_restore_on(stdout)
+}
+
+pub fn restore_on<W: Write>(output: fn() -> W) {
+    if let Err(err) = try_restore_on(output) {
+        std::eprintln!("Failed to restore terminal: {err}");
+    }
+}
+
+pub fn try_restore_on<W: Write>(output: fn() -> W) -> io::Result<()> {
     disable_raw_mode()?;
-    execute!(stdout(), LeaveAlternateScreen)?;
+    execute!(output(), LeaveAlternateScreen)?;
     Ok(())
 }
 
-fn set_panic_hook() {
+fn set_panic_hook<W: Write + 'static>(output: fn() -> W) {
     let hook = std::panic::take_hook();
 
     // hartley: replaced alloc with std since I'm not using no-std
     std::panic::set_hook(Box::new(move |info| {
-        restore();
+        restore_on(output);
         hook(info);
     }));
 }
@@ -0,0 +1,196 @@
+use std::io::{Write, stderr, stdout};
+use std::sync::mpsc::Receiver;
+
+use nucleo::Utf32String;
+use ratatui_core::{
+    layout::{Constraint, Layout},
+    style::Stylize,
+    terminal::{TerminalOptions, Viewport},
+    text::Line,
+};
+use ratatui_crossterm::crossterm::event::{self, KeyCode, KeyEventKind};
+
+use crate::ui::picker::Picker;
+use crate::ui::ratinit::{self, DefaultTerminal};
+
+const PADDED_CHEVRON: &str = "> ";
+const MAX_ITEMS: usize = 10;
+
+/// Asks the user to fuzzy pick one of `items` below a `title` line
+///
+/// Returns `None` if the user aborts with Esc.
+pub fn select<T: Clone + Send + Sync + 'static>(
+    title: &str,
+    items: Vec<T>,
+    formatter: fn(&T, &()) -> Utf32String,
+) -> anyhow::Result<Option<T>> {
+    Select::new(title, items, formatter).run()
+}
+
+/// A `select` with a starting query, a ranking bias, more items to come or drawn on stderr
+pub struct Select<'a, T: Clone + Send + Sync + 'static> {
+    title: &'a str,
+    picker: Picker<T, ()>,
+    len: usize,
+    search: String,
+    on_stderr: bool,
+    more: Option<Receiver<T>>,
+}
+
+impl<'a, T: Clone + Send + Sync + 'static> Select<'a, T> {
+    pub fn new(title: &'a str, items: Vec<T>, formatter: fn(&T, &()) -> Utf32String) -> Self {
+        let len = items.len();
+
+        let mut picker = Picker::new(formatter, ());
+        for item in items {
+            picker.push(item);
+        }
+
+        Self {
+            title,
+            picker,
+            len,
+            search: String::new(),
+            on_stderr: false,
+            more: None,
+        }
+    }
+
+    pub fn with_query(mut self, query: &str) -> Self {
+        self.search = query.into();
+        self.picker.push_char(query);
+        self
+    }
+
+    pub fn with_bias(self, bias: impl Fn(&T) -> f64 + 'static) -> Self {
+        Self {
+            picker: self.picker.with_bias(bias),
+            ..self
+        }
+    }
+
+    /// Lists the items sent on `more` as they arrive
+    pub fn with_more(self, more: Receiver<T>) -> Self {
+        Self {
+            more: Some(more),
+            ..self
+        }
+    }
+
+    /// Draws on stderr, so stdout can be captured by the caller
+    ///
+    /// The picker takes the whole screen then, since the inline viewport asks for the cursor
+    /// position on stdout.
+    pub fn on_stderr(self) -> Self {
+        Self {
+            on_stderr: true,
+            ..self
+        }
+    }
+
+    pub fn run(self) -> anyhow::Result<Option<T>> {
+        if self.on_stderr {
+            self.run_on(stderr)
+        } else {
+            self.run_on(stdout)
+        }
+    }
+
+    fn run_on<W: Write + 'static>(mut self, output: fn() -> W) -> anyhow::Result<Option<T>> {
+        // title, items, border and prompt, leaving room for items that arrive later
+        let len = if self.more.is_some() {
+            MAX_ITEMS
+        } else {
+            self.len
+        };
+        let height = len.min(MAX_ITEMS) + 3;
+
+        let terminal = if self.on_stderr {
+            ratinit::try_init_on(output)
+        } else {
+            ratinit::try_init_with_options_on(
+                output,
+                TerminalOptions {
+                    viewport: Viewport::Inline(height.try_into()?),
+                },
+            )
+        };
+        let mut terminal = match terminal {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                ratinit::restore_on(output);
+                return Err(e.into());
+            }
+        };
+
+        let result = run(
+            &mut terminal,
+            self.title,
+            &mut self.picker,
+            &mut self.search,
+            self.more.as_ref(),
+        );
+
+        // the terminal is restored even when clearing it fails
+        let cleared = terminal.clear();
+        ratinit::restore_on(output);
+        cleared?;
+
+        result
+    }
+}
+
+fn run<T: Clone + Send + Sync + 'static, W: Write>(
+    terminal: &mut DefaultTerminal<W>,
+    title: &str,
+    picker: &mut Picker<T, ()>,
+    search: &mut String,
+    more: Option<&Receiver<T>>,
+) -> anyhow::Result<Option<T>> {
+    loop {
+        for item in more.into_iter().flat_map(Receiver::try_iter) {
+            picker.push(item);
+        }
+
+        terminal.draw(|frame| {
+            let area = frame.area();
+            let layout = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Fill(1),
+                Constraint::Length(1),
+            ])
+            .split(area);
+
+            frame.render_widget(Line::from(title.bold()), layout[0]);
+            picker.render(layout[1], frame.buffer_mut());
+            frame.render_widget(
+                Line::from(vec![PADDED_CHEVRON.bold().magenta(), search.clone().bold()]),
+                layout[2],
+            );
+
+            // TODO: unwrap because the string length should not exceed u16
+            frame.set_cursor_position(((2 + search.len()).try_into().unwrap(), layout[2].y));
+        })?;
+
+        if event::poll(std::time::Duration::from_millis(16))?
+            && let event::Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char(c) => {
+                    search.push(c);
+                    picker.push_char(search);
+                }
+                KeyCode::Backspace => {
+                    search.pop();
+                    picker.pop_char(search);
+                }
+                KeyCode::Up => picker.inc_selection(),
+                KeyCode::Down => picker.dec_selection(),
+                KeyCode::Enter => return Ok(picker.selected_data()),
+                _ => (),
+            }
+        }
+    }
+}
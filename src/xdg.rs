@@ -0,0 +1,26 @@
+use std::env;
+use std::path::PathBuf;
+
+const APP: &str = "sdev";
+
+/// sdev's directory under `$XDG_DATA_HOME`, for state that should survive cache clearing
+pub fn data_dir() -> PathBuf {
+    base_dir("XDG_DATA_HOME", ".local/share").join(APP)
+}
+
+/// sdev's directory under `$XDG_CACHE_HOME`, for anything that can be rebuilt from scratch
+pub fn cache_dir() -> PathBuf {
+    base_dir("XDG_CACHE_HOME", ".cache").join(APP)
+}
+
+fn base_dir(var: &str, fallback: &str) -> PathBuf {
+    env::var_os(var)
+        .map(PathBuf::from)
+        // the spec says relative paths are invalid and should be ignored
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| {
+            env::home_dir()
+                .expect("unknown HOME directory")
+                .join(fallback)
+        })
+}